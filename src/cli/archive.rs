@@ -0,0 +1,157 @@
+//! Fetch templates as a single `.tar.gz` or `.zip` archive.
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::cli::utils::TempDir;
+
+/// Download an archive and extract it into `dest`.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the archive.
+/// * `dest` - The directory to extract into, which must not exist.
+pub async fn fetch(url: &str, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::cli::download;
+
+    let bytes: Vec<u8> = download::download_bytes(url.to_string())
+        .await
+        .map_err(|e| format!("Failed to download archive {}: {}", url, e))?;
+    extract(&bytes, dest).map_err(|e| format!("Failed to extract archive {}: {}", url, e).into())
+}
+
+/// Extract an archive into `dest`.
+///
+/// The format is detected from the archive's contents. If every entry is
+/// inside a single top-level directory, as in archives of a Git ref
+/// downloaded from GitHub or GitLab, the contents of that directory are
+/// extracted instead.
+///
+/// # Arguments
+///
+/// * `bytes` - The archive.
+/// * `dest` - The directory to extract into, which must not exist.
+pub fn extract(bytes: &[u8], dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let parent: &Path = dest.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let dir: TempDir = TempDir::new_in(parent, ".extract")?;
+
+    if bytes.starts_with(b"PK\x03\x04") {
+        zip::ZipArchive::new(Cursor::new(bytes))?.extract(dir.path())?;
+    } else if bytes.starts_with(&[0x1f, 0x8b]) {
+        let decoder = flate2::read::GzDecoder::new(bytes);
+        tar::Archive::new(decoder).unpack(dir.path())?;
+    } else {
+        return Err("Unsupported archive format, expected .tar.gz or .zip".into());
+    }
+
+    let root: PathBuf = single_child(dir.path())?.unwrap_or_else(|| dir.path().to_path_buf());
+    fs::rename(root, dest)?;
+    Ok(())
+}
+
+/// Get the only entry of a directory if it is itself a directory.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to check.
+fn single_child(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    let entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    match entries.as_slice() {
+        [entry] if entry.file_type()?.is_dir() => Ok(Some(entry.path())),
+        _ => Ok(None),
+    }
+}
+
+/// Check whether a template location looks like an archive URL.
+///
+/// # Arguments
+///
+/// * `location` - The location to check.
+pub fn is_archive_url(location: &str) -> bool {
+    let path: &str = location.split(['?', '#']).next().unwrap_or(location);
+    crate::cli::template::is_url(location)
+        && (path.ends_with(".tar.gz")
+            || path.ends_with(".tgz")
+            || path.ends_with(".zip")
+            || path.contains("/tarball/")
+            || path.contains("/zipball/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Build a `.tar.gz` with every file inside `repo-main/`.
+    fn tar_gz() -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (path, contents) in [
+            ("repo-main/README.md", "readme"),
+            ("repo-main/py/main.py", "main"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// Build a `.zip` with files at the top level.
+    fn zip() -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        writer.start_file("README.md", options).unwrap();
+        writer.write_all(b"readme").unwrap();
+        writer.start_file("src/main.py", options).unwrap();
+        writer.write_all(b"main").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let dest: &Path = Path::new("_test_extract_tar_gz");
+        extract(&tar_gz(), dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("README.md")).unwrap(),
+            "readme"
+        );
+        assert_eq!(fs::read_to_string(dest.join("py/main.py")).unwrap(), "main");
+        fs::remove_dir_all(dest).unwrap();
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let dest: &Path = Path::new("_test_extract_zip");
+        extract(&zip(), dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("src/main.py")).unwrap(),
+            "main"
+        );
+        fs::remove_dir_all(dest).unwrap();
+    }
+
+    #[test]
+    fn test_extract_unsupported() {
+        let dest: &Path = Path::new("_test_extract_unsupported");
+        assert!(extract(b"404: Not Found", dest).is_err());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_is_archive_url() {
+        assert!(is_archive_url(
+            "https://github.com/o/r/archive/refs/tags/v1.tar.gz"
+        ));
+        assert!(is_archive_url(
+            "https://api.github.com/repos/o/r/zipball/main"
+        ));
+        assert!(!is_archive_url("https://github.com/o/r"));
+        assert!(!is_archive_url("./template.zip"));
+    }
+}
@@ -1,5 +1,6 @@
 extern crate alloc;
 use alloc::borrow::Cow;
+use std::path::Path;
 use std::process::{Command, Output};
 
 /// Initialise a new Git repository.
@@ -7,14 +8,249 @@ use std::process::{Command, Output};
 /// # Arguments
 ///
 /// * `name` - The name of the project.
-pub fn git_init(name: &String) {
-    let output: Output = Command::new("git")
+pub fn git_init(name: &String) -> Result<(), String> {
+    run(Command::new("git").arg("init").arg("--quiet").arg(name))
+}
+
+/// Point a new Git repository's `HEAD` at a branch.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+/// * `branch` - The name of the branch.
+pub fn git_set_branch(dir: &str, branch: &str) -> Result<(), String> {
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .arg("symbolic-ref")
+            .arg("HEAD")
+            .arg(format!("refs/heads/{}", branch)),
+    )
+}
+
+/// Add a remote to a Git repository.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+/// * `name` - The name of the remote, e.g. `origin`.
+/// * `url` - The URL of the remote.
+pub fn git_add_remote(dir: &str, name: &str, url: &str) -> Result<(), String> {
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .arg("remote")
+            .arg("add")
+            .arg(name)
+            .arg(url),
+    )
+}
+
+/// Stage every file in a Git repository and commit them.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+/// * `message` - The commit message.
+pub fn git_commit(dir: &str, message: &str) -> Result<(), String> {
+    run_git(Command::new("git").arg("-C").arg(dir).arg("add").arg("-A"))?;
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .arg("commit")
+            .arg("--quiet")
+            .arg("-m")
+            .arg(message),
+    )
+}
+
+/// Push the current branch to `origin` and track it.
+///
+/// # Arguments
+///
+/// * `dir` - The repository.
+pub fn git_push(dir: &str) -> Result<(), String> {
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .arg("push")
+            .arg("--quiet")
+            .arg("-u")
+            .arg("origin")
+            .arg("HEAD"),
+    )
+}
+
+/// Shallow clone a Git repository.
+///
+/// Branches and tags are cloned with `--depth 1`. Anything else, such as a
+/// commit SHA, falls back to a full clone followed by a checkout.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the repository.
+/// * `reference` - The branch, tag or commit to check out.
+/// * `dest` - The directory to clone into.
+pub fn git_clone(url: &str, reference: Option<&str>, dest: &Path) -> Result<(), String> {
+    let mut shallow: Command = Command::new("git");
+    shallow.arg("clone").arg("--quiet").arg("--depth").arg("1");
+    if let Some(reference) = reference {
+        shallow.arg("--branch").arg(reference);
+    }
+    let error: String = match run_git(shallow.arg(url).arg(dest)) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let reference: &str = match reference {
+        Some(reference) => reference,
+        None => return Err(format!("Failed to clone {}: {}", url, error)),
+    };
+    if dest.exists() {
+        std::fs::remove_dir_all(dest).map_err(|e| e.to_string())?;
+    }
+    run_git(
+        Command::new("git")
+            .arg("clone")
+            .arg("--quiet")
+            .arg(url)
+            .arg(dest),
+    )
+    .map_err(|e| format!("Failed to clone {}: {}", url, e))?;
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dest)
+            .arg("checkout")
+            .arg("--quiet")
+            .arg(reference),
+    )
+    .map_err(|e| format!("Failed to check out `{}` from {}: {}", reference, url, e))
+}
+
+/// Run a Git command, returning its stderr if it fails.
+///
+/// # Arguments
+///
+/// * `cmd` - The command to run.
+fn run_git(cmd: &mut Command) -> Result<(), String> {
+    let output: Output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Create a Python virtual environment.
+///
+/// # Arguments
+///
+/// * `name` - The name of the project.
+pub fn make_virtualenv(name: &String) -> Result<(), String> {
+    run(Command::new("python3")
+        .arg("-m")
+        .arg("venv")
+        .arg(format!("{}/venv", name)))
+}
+
+/// Initialise a Cargo package in an existing directory.
+///
+/// # Arguments
+///
+/// * `dir` - The project directory.
+/// * `name` - The name of the package.
+/// * `vcs` - Whether to initialise a Git repository. Passed to Cargo
+///   explicitly, as it skips Git inside an existing work tree by default.
+pub fn cargo_init(dir: &String, name: &str, vcs: bool) -> Result<(), String> {
+    let vcs: &str = if vcs { "git" } else { "none" };
+    run(Command::new("cargo")
         .arg("init")
+        .arg("--name")
         .arg(name)
+        .arg("--vcs")
+        .arg(vcs)
+        .arg(dir))
+}
+
+/// Run a template hook through the shell in a directory.
+///
+/// The hook's output is streamed rather than printed once it exits.
+///
+/// # Arguments
+///
+/// * `hook` - The shell command.
+/// * `dir` - The directory to run it in.
+pub fn run_hook(hook: &str, dir: &str) -> Result<(), String> {
+    let mut cmd: Command = if cfg!(windows) {
+        let mut cmd: Command = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd: Command = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    let status: std::process::ExitStatus = cmd
+        .arg(hook)
+        .current_dir(dir)
+        .status()
+        .map_err(|e| format!("Failed to run `{}`: {}", hook, e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{}` failed with {}", hook, status))
+    }
+}
+
+/// Run a command, printing its output and failing if it exits non-zero.
+///
+/// # Arguments
+///
+/// * `cmd` - The command to run.
+fn run(cmd: &mut Command) -> Result<(), String> {
+    let program: String = cmd.get_program().to_string_lossy().to_string();
+    let output: Output = cmd
         .output()
-        .expect("Failed to execute process");
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let status: std::process::ExitStatus = output.status;
 
-    print_cmd_out(output)
+    print_cmd_out(output);
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{}` failed with {}", program, status))
+    }
+}
+
+/// Read a value from the user's Git config.
+///
+/// Returns `None` if Git is unavailable or the key is not set.
+///
+/// # Arguments
+///
+/// * `key` - The config key, e.g. `user.name`.
+pub fn git_config(key: &str) -> Option<String> {
+    let output: Output = Command::new("git")
+        .arg("config")
+        .arg("--get")
+        .arg(key)
+        .output()
+        .ok()?;
+
+    let value: String = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !value.is_empty() {
+        Some(value)
+    } else {
+        None
+    }
 }
 
 /// Print the output of a command.
@@ -39,12 +275,93 @@ mod tests {
     use super::*;
     use std::fs;
 
+    #[test]
+    fn test_run_fails_on_non_zero_exit() {
+        assert!(run(Command::new("git").arg("not-a-command")).is_err());
+        assert!(run(&mut Command::new("_test_missing_program")).is_err());
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let dir: &str = "_test_run_hook";
+        fs::create_dir_all(dir).unwrap();
+        run_hook("echo hooked > out.txt", dir).unwrap();
+        assert_eq!(
+            fs::read_to_string("_test_run_hook/out.txt").unwrap(),
+            "hooked\n"
+        );
+        assert!(run_hook("exit 3", dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn test_git_init() {
         let name: String = "_test_git_init".to_string();
-        git_init(&name);
+        git_init(&name).unwrap();
         let exists: bool = fs::metadata(&name).is_ok();
-        assert_eq!(exists, true);
+        assert!(exists);
+        fs::remove_dir_all(name).unwrap();
+    }
+
+    #[test]
+    fn test_git_set_branch_and_remote() {
+        let name: String = "_test_git_set_branch".to_string();
+        git_init(&name).unwrap();
+        git_set_branch(&name, "trunk").unwrap();
+        git_add_remote(&name, "origin", "git@example.com:org/app.git").unwrap();
+
+        assert_eq!(git(&name, &["symbolic-ref", "HEAD"]), "refs/heads/trunk");
+        assert_eq!(
+            git(&name, &["remote", "get-url", "origin"]),
+            "git@example.com:org/app.git"
+        );
+        assert!(git_add_remote(&name, "origin", "git@example.com:org/app.git").is_err());
         fs::remove_dir_all(name).unwrap();
     }
+
+    /// Run a Git command in a directory, panicking if it fails.
+    fn git(dir: &str, args: &[&str]) -> String {
+        let output: Output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_git_clone() {
+        let repo: String = "_test_git_clone_repo".to_string();
+        git_init(&repo).unwrap();
+        fs::write(format!("{}/file.txt", repo), "v1").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "v1"]);
+        git(&repo, &["tag", "v1"]);
+        let sha: String = git(&repo, &["rev-parse", "HEAD"]);
+        fs::write(format!("{}/file.txt", repo), "v2").unwrap();
+        git(&repo, &["commit", "-q", "-am", "v2"]);
+
+        let url: String = format!("file://{}", fs::canonicalize(&repo).unwrap().display());
+        for (reference, expected) in [(None, "v2"), (Some("v1"), "v1"), (Some(sha.as_str()), "v1")]
+        {
+            let dest: &Path = Path::new("_test_git_clone_dest");
+            git_clone(&url, reference, dest).unwrap();
+            assert_eq!(fs::read_to_string(dest.join("file.txt")).unwrap(), expected);
+            fs::remove_dir_all(dest).unwrap();
+        }
+        assert!(git_clone(&url, Some("missing"), Path::new("_test_git_clone_dest")).is_err());
+        let error: String = git_clone(
+            "file:///dev-cli-missing",
+            None,
+            Path::new("_test_git_clone_dest"),
+        )
+        .unwrap_err();
+        assert!(error.len() > "Failed to clone file:///dev-cli-missing: ".len());
+
+        fs::remove_dir_all(repo).unwrap();
+        let _ = fs::remove_dir_all("_test_git_clone_dest");
+    }
 }
@@ -0,0 +1,612 @@
+//! Cache of downloaded templates.
+//!
+//! Every remote template source is fetched into `~/.dev-config/cache`,
+//! keyed by its URL and ref, and projects are copied from there. A cached
+//! copy is reused until `--refresh` fetches a fresh one, which falls back
+//! to the cached copy if fetching fails. Git templates pinned to a full
+//! commit SHA never change, so they are never fetched again.
+//!
+//! Each entry is a directory containing the template's files in `files` and
+//! a `source.yaml` describing where they came from.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::progress;
+use crate::cli::template::{Source, MANIFEST_FILE};
+use crate::cli::utils::TempDir;
+
+/// The file describing a cache entry.
+const META_FILE: &str = "source.yaml";
+
+/// How the cache is used when fetching a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Use the cached copy, only fetching sources that are not cached.
+    Reuse,
+    /// Fetch a fresh copy, falling back to the cached copy if that fails.
+    Refresh,
+    /// Only use the cache, never the network.
+    Offline,
+}
+
+/// Implement the `Mode` enum.
+impl Mode {
+    /// Get the mode for the `--offline` and `--refresh` flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `offline` - Only use the cache.
+    /// * `refresh` - Fetch a fresh copy of every template.
+    pub fn new(offline: bool, refresh: bool) -> Self {
+        match (offline, refresh) {
+            (true, _) => Mode::Offline,
+            (_, true) => Mode::Refresh,
+            _ => Mode::Reuse,
+        }
+    }
+}
+
+/// A description of a cache entry.
+///
+/// # Fields
+///
+/// * `source` - The URL the entry was fetched from.
+/// * `reference` - The Git ref the entry was fetched at, if any.
+/// * `fetched` - When the entry was fetched, in seconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Meta {
+    pub source: String,
+    pub reference: Option<String>,
+    pub fetched: u64,
+}
+
+/// A cache entry.
+///
+/// # Fields
+///
+/// * `key` - The name of the entry's directory.
+/// * `meta` - The description of the entry.
+/// * `size` - The total size of the entry's files in bytes.
+#[derive(Debug)]
+pub struct Entry {
+    pub key: String,
+    pub meta: Meta,
+    pub size: u64,
+}
+
+/// Implement the `print` function for `Entry`.
+impl Entry {
+    /// Print an entry to the console.
+    pub fn print(&self) {
+        let reference: String = match &self.meta.reference {
+            Some(reference) => format!(" @ {}", reference),
+            None => String::new(),
+        };
+        println!(
+            "\x1b[1m{}\x1b[0m\n    {}{}\n    {}, fetched {}\n",
+            self.key,
+            self.meta.source,
+            reference,
+            format_size(self.size),
+            format_age(now().saturating_sub(self.meta.fetched)),
+        )
+    }
+}
+
+/// Get the path to the cache directory.
+pub fn dir() -> PathBuf {
+    let config: PathBuf = crate::cli::config::Config::dir();
+    config.parent().unwrap().join("cache")
+}
+
+/// Fetch a template source, returning the directory its files are in.
+///
+/// `Source::Local` is returned as-is. Any other source is fetched into the
+/// cache unless it is already cached, or `mode` asks for a fresh copy. A
+/// `Source::Remote` only counts as cached if every file in `files` is, and
+/// a cached entry missing some of them only has those downloaded into it,
+/// so templates used with different answers share one entry.
+///
+/// # Arguments
+///
+/// * `source` - The template source.
+/// * `files` - The files needed from a `Source::Remote`.
+/// * `mode` - How to use the cache.
+pub async fn fetch(
+    source: &Source,
+    files: &[&str],
+    mode: Mode,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let (url, reference, subdir): (&str, Option<&String>, Option<&String>) = match source {
+        Source::Local(path) => return Ok(path.clone()),
+        Source::Remote(url) => (url, None, None),
+        Source::Archive { url, subdir } => (url, None, subdir.as_ref()),
+        Source::Git {
+            url,
+            reference,
+            subdir,
+        } => (url, reference.as_ref(), subdir.as_ref()),
+    };
+
+    let entry: PathBuf = dir().join(key(url, reference.map(String::as_str)));
+    let root: PathBuf = entry.join("files");
+    let missing: Vec<&str> = files
+        .iter()
+        .copied()
+        .filter(|file| !root.join(file).exists())
+        .collect();
+    let fetched: bool = entry.join(META_FILE).exists();
+    let cached: bool = fetched && missing.is_empty();
+    let pinned: bool = reference.is_some_and(|r| is_commit_sha(r));
+    let stale: bool = match mode {
+        Mode::Reuse => !cached,
+        Mode::Refresh => !(cached && pinned),
+        Mode::Offline => false,
+    };
+
+    let remote: bool = matches!(source, Source::Remote(_));
+    if stale && remote && fetched && mode == Mode::Reuse {
+        complete(&root, url, &missing).await?;
+    } else if stale {
+        let result = refresh(&entry, url, reference, |staging| async move {
+            match source {
+                Source::Remote(base_url) => {
+                    progress::step(&format!(
+                        "Downloading {} file(s) from {}",
+                        files.len(),
+                        base_url
+                    ));
+                    crate::cli::download::download_files(base_url, files.to_vec(), &staging)
+                        .await
+                        .map_err(|e| e.into())
+                }
+                Source::Archive { url, .. } => {
+                    progress::step(&format!("Downloading archive {}", url));
+                    crate::cli::archive::fetch(url, &staging).await
+                }
+                Source::Git { url, reference, .. } => {
+                    progress::step(&format!("Cloning {}", url));
+                    crate::cli::bash::git_clone(url, reference.as_deref(), &staging)?;
+                    Ok(fs::remove_dir_all(staging.join(".git"))?)
+                }
+                Source::Local(_) => unreachable!(),
+            }
+        })
+        .await;
+
+        if let Err(e) = result {
+            if !cached {
+                return Err(e);
+            }
+            eprintln!("{}\nUsing cached copy of {}", e, url);
+        }
+    } else if !cached {
+        return Err(format!("{} is not cached, run without `--offline` to fetch it", url).into());
+    }
+
+    match subdir {
+        Some(subdir) if !root.join(subdir).is_dir() => {
+            Err(format!("Directory `{}` not found in {}", subdir, url).into())
+        }
+        Some(subdir) => Ok(root.join(subdir)),
+        None => Ok(root),
+    }
+}
+
+/// Check whether a source has been fetched into the cache.
+///
+/// Returns `None` for a local directory, which is never cached.
+///
+/// # Arguments
+///
+/// * `source` - The source to check.
+pub fn is_cached(source: &Source) -> Option<bool> {
+    let (url, reference) = match source {
+        Source::Local(_) => return None,
+        Source::Remote(url) | Source::Archive { url, .. } => (url, None),
+        Source::Git { url, reference, .. } => (url, reference.as_deref()),
+    };
+    Some(dir().join(key(url, reference)).join(META_FILE).exists())
+}
+
+/// Fetch a manifest from a URL, caching it for offline use.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the manifest.
+/// * `mode` - How to use the cache.
+/// * `retry` - Whether to retry transient download failures.
+pub async fn fetch_manifest(
+    url: &str,
+    mode: Mode,
+    retry: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let entry: PathBuf = dir().join(key(url, None));
+    let path: PathBuf = entry.join("files").join(MANIFEST_FILE);
+    let stale: bool = match mode {
+        Mode::Reuse => !path.exists(),
+        Mode::Refresh => true,
+        Mode::Offline => false,
+    };
+
+    if stale {
+        let result = refresh(&entry, url, None, |staging| async move {
+            let contents: String =
+                crate::cli::download::download_text(url.to_string(), retry).await?;
+            fs::create_dir_all(&staging)?;
+            Ok(fs::write(staging.join(MANIFEST_FILE), contents)?)
+        })
+        .await;
+        if let Err(e) = result {
+            if !path.exists() {
+                return Err(e);
+            }
+        }
+    }
+    fs::read_to_string(&path)
+        .map_err(|_| format!("{} is not cached, run without `--offline` to fetch it", url).into())
+}
+
+/// Download the files missing from a cached `Source::Remote` entry into it.
+///
+/// The other files in the entry are kept as they are.
+///
+/// # Arguments
+///
+/// * `root` - The directory the entry's files are in.
+/// * `base_url` - The base URL the files are downloaded from.
+/// * `files` - The missing files.
+async fn complete(
+    root: &Path,
+    base_url: &str,
+    files: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    progress::step(&format!(
+        "Downloading {} missing file(s) from {}",
+        files.len(),
+        base_url
+    ));
+    let staging: TempDir = TempDir::new_in(&dir(), ".staging")?;
+    let downloaded: PathBuf = staging.path().join("files");
+    crate::cli::download::download_files(base_url, files.to_vec(), &downloaded).await?;
+    for file in files {
+        let path: PathBuf = root.join(file);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::rename(downloaded.join(file), path)?;
+    }
+    Ok(())
+}
+
+/// Fetch a fresh copy of a cache entry, replacing any existing copy.
+///
+/// The entry is only replaced once `fetch` has succeeded.
+///
+/// # Arguments
+///
+/// * `entry` - The entry's directory.
+/// * `url` - The URL the entry is fetched from.
+/// * `reference` - The Git ref the entry is fetched at.
+/// * `fetch` - Fetches the files into the path it is given, which does not exist yet.
+async fn refresh<F, Fut>(
+    entry: &Path,
+    url: &str,
+    reference: Option<&String>,
+    fetch: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn std::error::Error>>>,
+{
+    let staging: TempDir = TempDir::new_in(&dir(), ".staging")?;
+    fetch(staging.path().join("files")).await?;
+
+    let meta: Meta = Meta {
+        source: url.to_string(),
+        reference: reference.cloned(),
+        fetched: now(),
+    };
+    fs::write(
+        staging.path().join(META_FILE),
+        serde_yaml::to_string(&meta)?,
+    )?;
+
+    if entry.exists() {
+        fs::remove_dir_all(entry)?;
+    }
+    fs::rename(staging.path(), entry)?;
+    Ok(())
+}
+
+/// List every entry in the cache, sorted by key.
+pub fn list() -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let dir: PathBuf = dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for item in fs::read_dir(dir)? {
+        let item: fs::DirEntry = item?;
+        let Ok(contents) = fs::read_to_string(item.path().join(META_FILE)) else {
+            continue;
+        };
+        entries.push(Entry {
+            key: item.file_name().to_string_lossy().to_string(),
+            meta: serde_yaml::from_str(&contents)?,
+            size: dir_size(&item.path())?,
+        });
+    }
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(entries)
+}
+
+/// Remove entries from the cache.
+///
+/// Returns the number of entries removed.
+///
+/// # Arguments
+///
+/// * `key` - Only remove this entry. Removes everything if `None`.
+pub fn clear(key: Option<&str>) -> Result<usize, Box<dyn std::error::Error>> {
+    let dir: PathBuf = dir();
+    match key {
+        Some(key) => {
+            let entry: PathBuf = dir.join(key);
+            if key.contains(['/', '\\']) || !entry.join(META_FILE).exists() {
+                return Err(format!("No cache entry `{}`", key).into());
+            }
+            fs::remove_dir_all(entry)?;
+            Ok(1)
+        }
+        None => {
+            let count: usize = list()?.len();
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+            Ok(count)
+        }
+    }
+}
+
+/// Build the cache key for a URL and ref.
+///
+/// A readable slug of the URL followed by a hash of the URL and ref.
+///
+/// # Arguments
+///
+/// * `url` - The URL.
+/// * `reference` - The Git ref, if any.
+fn key(url: &str, reference: Option<&str>) -> String {
+    let id: String = match reference {
+        Some(reference) => format!("{}@{}", url, reference),
+        None => url.to_string(),
+    };
+
+    let slug: String = id
+        .split("://")
+        .last()
+        .unwrap_or(&id)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    let slug: String = slug.chars().take(48).collect();
+
+    // 64-bit FNV-1a, which unlike `DefaultHasher` is stable across releases.
+    let hash: u64 = id.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{}-{:08x}", slug, hash as u32)
+}
+
+/// Check whether a Git ref is a full commit SHA.
+///
+/// # Arguments
+///
+/// * `reference` - The Git ref.
+fn is_commit_sha(reference: &str) -> bool {
+    reference.len() == 40 && reference.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Get the total size of the files in a directory.
+///
+/// # Arguments
+///
+/// * `path` - The directory.
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size: u64 = 0;
+    for entry in fs::read_dir(path)? {
+        let entry: fs::DirEntry = entry?;
+        let metadata: fs::Metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Format a size in bytes for display.
+///
+/// # Arguments
+///
+/// * `bytes` - The size in bytes.
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Format an age in seconds for display.
+///
+/// # Arguments
+///
+/// * `secs` - The age in seconds.
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3_599 => format!("{} minute(s) ago", secs / 60),
+        3_600..=86_399 => format!("{} hour(s) ago", secs / 3_600),
+        _ => format!("{} day(s) ago", secs / 86_400),
+    }
+}
+
+/// Get the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        let key: String = key("https://github.com/org/py-template.git", Some("v1"));
+        assert!(key.starts_with("github-com-org-py-template-git-v1-"));
+        assert_ne!(
+            key,
+            super::key("https://github.com/org/py-template.git", Some("v2"))
+        );
+        assert_eq!(
+            key,
+            super::key("https://github.com/org/py-template.git", Some("v1"))
+        );
+    }
+
+    #[test]
+    fn test_is_commit_sha() {
+        assert!(is_commit_sha("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_commit_sha("main"));
+        assert!(!is_commit_sha("0123456"));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KiB");
+        assert_eq!(format_age(30), "just now");
+        assert_eq!(format_age(7_200), "2 hour(s) ago");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_local() {
+        let source: Source = Source::Local(PathBuf::from("/srv/templates/py"));
+        let path: PathBuf = fetch(&source, &[], Mode::Offline).await.unwrap();
+        assert_eq!(path, PathBuf::from("/srv/templates/py"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_git() {
+        let temp = TempDir::new_in(Path::new("."), "_test_cache_fetch_git").unwrap();
+        let repo: &str = temp.path().to_str().unwrap();
+        crate::cli::bash::git_init(&repo.to_string()).unwrap();
+        fs::create_dir(format!("{}/sub", repo)).unwrap();
+        fs::write(format!("{}/sub/file.txt", repo), "v1").unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "v1"]);
+
+        let url: String = format!("file://{}", fs::canonicalize(repo).unwrap().display());
+        let _entry = Cleared(key(&url, None));
+        let source = |subdir: &str| Source::Git {
+            url: url.clone(),
+            reference: None,
+            subdir: Some(subdir.to_string()),
+        };
+        let sub: PathBuf = fetch(&source("sub"), &[], Mode::Reuse).await.unwrap();
+        assert_eq!(fs::read_to_string(sub.join("file.txt")).unwrap(), "v1");
+
+        fs::write(format!("{}/sub/file.txt", repo), "v2").unwrap();
+        git(repo, &["commit", "-q", "-am", "v2"]);
+        for (mode, expected) in [
+            (Mode::Offline, "v1"),
+            (Mode::Reuse, "v1"),
+            (Mode::Refresh, "v2"),
+        ] {
+            let sub: PathBuf = fetch(&source("sub"), &[], mode).await.unwrap();
+            assert_eq!(fs::read_to_string(sub.join("file.txt")).unwrap(), expected);
+        }
+
+        let error: String = fetch(&source("missing"), &[], Mode::Offline)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, format!("Directory `missing` not found in {}", url));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_remote() {
+        // A server that answers a single request, so only missing files can
+        // be downloaded.
+        let listener: std::net::TcpListener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url: &str = &format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            use std::io::{Read, Write};
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            let body: &str = "gitlab";
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        let _entry = Cleared(key(url, None));
+        let entry: PathBuf = dir().join(key(url, None));
+        refresh(&entry, url, None, |staging| async move {
+            fs::create_dir_all(staging.join(".github"))?;
+            Ok(fs::write(staging.join(".github/ci.yml"), "ci")?)
+        })
+        .await
+        .unwrap();
+
+        let source: Source = Source::Remote(url.to_string());
+        let files: [&str; 2] = [".github/ci.yml", ".gitlab-ci.yml"];
+        let root: PathBuf = fetch(&source, &files[..1], Mode::Offline).await.unwrap();
+        assert_eq!(root, entry.join("files"));
+        assert!(fetch(&source, &files, Mode::Offline).await.is_err());
+
+        assert_eq!(fetch(&source, &files, Mode::Reuse).await.unwrap(), root);
+        assert_eq!(fs::read_to_string(root.join(files[0])).unwrap(), "ci");
+        assert_eq!(fs::read_to_string(root.join(files[1])).unwrap(), "gitlab");
+        assert_eq!(fetch(&source, &files, Mode::Offline).await.unwrap(), root);
+    }
+
+    /// A cache entry that is cleared when dropped, even if a test fails.
+    struct Cleared(String);
+
+    /// Implement the `Drop` trait for `Cleared`.
+    impl Drop for Cleared {
+        /// Clear the entry and remove the test cache directories once empty.
+        fn drop(&mut self) {
+            let _ = clear(Some(&self.0));
+            let _ = fs::remove_dir(dir());
+            let _ = fs::remove_dir(dir().parent().unwrap());
+        }
+    }
+
+    /// Run a Git command in a directory, panicking if it fails.
+    fn git(dir: &str, args: &[&str]) {
+        let status: std::process::ExitStatus = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+}
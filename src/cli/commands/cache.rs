@@ -0,0 +1,24 @@
+use clap::{Arg, Command};
+
+/// Operations for managing the template cache.
+///
+/// # Subcommands
+/// * `list` - List cached templates
+/// * `clear` - Remove cached templates
+pub fn cache() -> Command {
+    Command::new("cache")
+        .about("Operations for managing the template cache")
+        .subcommand(Command::new("list").about("List cached templates"))
+        .subcommand(clear())
+        .arg_required_else_help(true)
+}
+
+/// Remove cached templates.
+///
+/// # Arguments
+/// * `key` - Key of the entry to remove, removes everything if omitted
+fn clear() -> Command {
+    Command::new("clear")
+        .about("Remove cached templates")
+        .arg(Arg::new("key").help("Key of the entry to remove, removes everything if omitted"))
+}
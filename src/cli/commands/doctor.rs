@@ -0,0 +1,6 @@
+use clap::Command;
+
+/// Check the tools templates need are installed.
+pub fn doctor() -> Command {
+    Command::new("doctor").about("Check the tools templates need are installed")
+}
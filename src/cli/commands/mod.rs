@@ -1,5 +1,9 @@
+mod cache;
+mod doctor;
 mod project;
 mod search;
 
+pub use cache::cache;
+pub use doctor::doctor;
 pub use project::project;
 pub use search::search;
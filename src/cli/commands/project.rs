@@ -1,13 +1,19 @@
-use clap::{arg, Arg, Command};
+use clap::{arg, Arg, ArgAction, Command};
+
+use crate::cli::config::Config;
+use crate::cli::template::registry;
+use crate::cli::Conflict;
 
 /// Operations for creating and managing projects.
 ///
 /// # Subcommands
 /// * `new` - Create a new project
+/// * `list-langs` - List the languages a project can be created for
 pub fn project() -> Command {
     Command::new("project")
         .about("Operations for creating and managing projects")
         .subcommand(new())
+        .subcommand(list_langs())
         .arg_required_else_help(true)
 }
 
@@ -15,11 +21,126 @@ pub fn project() -> Command {
 ///
 /// # Arguments
 /// * `name` - Name of the project
-/// * `-l --lang <String>` - Language to generate a project for
+/// * `-l --lang <String>` - Language to generate a project for, or an alias
+///   such as `python`
+/// * `-t --template <String>` - Template directory, archive URL or Git URL to generate a project from
+/// * `--ref <String>` - Branch, tag or commit of a Git template to use
+/// * `--var <KEY=VALUE>` - Template variable, may be repeated
+/// * `--offline` - Only use cached templates
+/// * `--refresh` - Fetch fresh copies of cached templates
+/// * `--path <DIR>` - Directory to create the project in, defaulting to the
+///   name under the configured projects root
+/// * `--into <DIR>` - Existing directory to generate the project into
+/// * `--force` - Generate into the project directory even if it exists
+/// * `--conflict <POLICY>` - What to do with files that already exist
+/// * `--dry-run` - Print the files and commands without creating anything
+/// * `--no-hooks` - Skip the template's hook commands
+/// * `--answers <FILE>` - YAML file of answers to the template's questions
+/// * `--no-git` - Don't initialise a Git repository
+/// * `--branch <NAME>` - Initial branch of the Git repository
+/// * `--commit` - Commit every file once the project is created
+/// * `--no-commit` - Don't create an initial commit
+/// * `--message <MESSAGE>` - Message of the initial commit, implies `--commit`
+/// * `--remote <URL>` - URL of the `origin` remote, may use `{name}` and `{org}`
+/// * `--org <ORG>` - Organisation substituted for `{org}` in the remote URL
+///   and to create the remote repository in
+/// * `--create-remote` - Create the remote repository on the forge and push
+/// * `--private` - Make the created remote repository private
+/// * `--public` - Make the created remote repository public
+/// * `--description <TEXT>` - Description of the created remote repository
 fn new() -> Command {
     Command::new("new")
         .about("Create a new project")
         .arg(Arg::new("name").required(true))
-        .arg(arg!(-l --lang <String> "Language to generate a project for"))
+        .arg(
+            arg!(-l --lang <String> "Language to generate a project for")
+                .value_parser(parse_lang),
+        )
+        .arg(
+            arg!(-t --template <String> "Template directory, archive URL or Git URL to generate a project from")
+                .conflicts_with("lang"),
+        )
+        .arg(arg!(--ref <String> "Branch, tag or commit of a Git template to use"))
+        .arg(
+            Arg::new("var")
+                .long("var")
+                .value_name("KEY=VALUE")
+                .action(ArgAction::Append)
+                .help("Set a template variable, may be repeated"),
+        )
+        .arg(arg!(--offline "Only use cached templates, never the network"))
+        .arg(
+            arg!(--refresh "Fetch fresh copies of cached templates")
+                .conflicts_with("offline"),
+        )
+        .arg(arg!(--path <DIR> "Directory to create the project in, defaults to the name"))
+        .arg(
+            arg!(--into <DIR> "Existing directory to generate the project into")
+                .conflicts_with("path"),
+        )
+        .arg(arg!(--force "Generate into the project directory even if it exists"))
+        .arg(
+            arg!(--conflict <POLICY> "What to do with files that already exist")
+                .value_parser(Conflict::VALUES)
+                .default_value("prompt"),
+        )
+        .arg(arg!(--"dry-run" "Print the files and commands without creating anything"))
+        .arg(arg!(--"no-hooks" "Skip the template's hook commands"))
+        .arg(arg!(--answers <FILE> "YAML file of answers to the template's questions"))
+        .arg(
+            arg!(--"no-git" "Don't initialise a Git repository").conflicts_with_all([
+                "branch",
+                "commit",
+                "message",
+                "remote",
+                "org",
+                "create-remote",
+            ]),
+        )
+        .arg(arg!(--branch <NAME> "Initial branch of the Git repository"))
+        .arg(arg!(--commit "Commit every file once the project is created"))
+        .arg(arg!(--"no-commit" "Don't create an initial commit").conflicts_with_all(["commit", "message"]))
+        .arg(arg!(--message <MESSAGE> "Message of the initial commit, implies --commit"))
+        .arg(arg!(--remote <URL> "URL of the origin remote, may use {name} and {org}"))
+        .arg(arg!(--org <ORG> "Organisation for {org} in the remote URL and the created repository"))
+        .arg(
+            arg!(--"create-remote" "Create the remote repository on the forge and push to it")
+                .conflicts_with("no-commit"),
+        )
+        .arg(arg!(--private "Make the created remote repository private").requires("create-remote"))
+        .arg(
+            arg!(--public "Make the created remote repository public")
+                .requires("create-remote")
+                .conflicts_with("private"),
+        )
+        .arg(
+            arg!(--description <TEXT> "Description of the created remote repository")
+                .requires("create-remote"),
+        )
         .arg_required_else_help(true)
 }
+
+/// Parse a `--lang` value into a registered language key.
+///
+/// If the config cannot be loaded the value is passed through so the
+/// config error is reported when the project is created.
+///
+/// # Arguments
+///
+/// * `value` - The value passed to `--lang`.
+fn parse_lang(value: &str) -> Result<String, String> {
+    match Config::load_sync() {
+        Ok(config) => registry::resolve(value, &config),
+        Err(_) => Ok(value.to_string()),
+    }
+}
+
+/// List the languages a project can be created for.
+///
+/// # Arguments
+/// * `--json` - Print the languages as JSON
+fn list_langs() -> Command {
+    Command::new("list-langs")
+        .about("List the languages a project can be created for")
+        .arg(arg!(--json "Print the languages as JSON"))
+}
@@ -0,0 +1,455 @@
+//! Config for dev-cli.
+//!
+//! Read from `~/.dev-config/config.yaml`, the same file used by the search
+//! command. Every section is optional. Set `DEV_CLI_HOME` to use another
+//! directory instead of `~/.dev-config`.
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::cli::template::Manifest;
+
+/// The environment variable that overrides the config directory.
+const HOME_VAR: &str = "DEV_CLI_HOME";
+
+/// The config directory used by tests, relative to the current directory.
+const TEST_HOME: &str = "_test_dev_home";
+
+/// The config for dev-cli.
+///
+/// # Fields
+///
+/// * `languages` - User-defined languages, keyed by the value passed to `--lang`.
+/// * `download` - Settings for downloading templates.
+/// * `projects_root` - The directory new projects are created in when no
+///   `--path` is given, e.g. `~/code`. Defaults to the current directory.
+/// * `git` - Settings for the Git repository of new projects.
+/// * `forge` - Settings for creating remote repositories.
+#[derive(Deserialize, Debug, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub languages: HashMap<String, Language>,
+    #[serde(default)]
+    pub download: Download,
+    pub projects_root: Option<String>,
+    #[serde(default)]
+    pub git: Git,
+    #[serde(default)]
+    pub forge: Forge,
+}
+
+/// Settings for downloading templates.
+///
+/// ```yaml
+/// download:
+///   timeout: 30
+///   retries: 3
+///   max_concurrency: 8
+///   token: glpat-xxxx
+///   token_hosts: [git.example.com]
+///   proxy: http://proxy.example.com:3128
+///   no_proxy: localhost,.internal
+///   ca_bundle: /etc/ssl/certs/corporate-ca.pem
+/// ```
+///
+/// Without `proxy`, the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+/// environment variables are used.
+///
+/// # Fields
+///
+/// * `timeout` - Seconds before a single request is abandoned.
+/// * `retries` - How many times a request is retried after a timeout,
+///   connection error, `429` or `5xx` response.
+/// * `max_concurrency` - The most files downloaded at once.
+/// * `token` - A bearer token sent to `token_hosts`, overridden by the
+///   `DEV_CLI_TOKEN` environment variable.
+/// * `token_hosts` - Hosts, and their subdomains, the token is sent to. The
+///   token is not sent anywhere if empty, and a warning is printed on the
+///   first download when a token is set, so list the template hosts, e.g.
+///   `[github.com]`, whenever `token` or `DEV_CLI_TOKEN` is used.
+/// * `proxy` - A proxy URL used for every request.
+/// * `no_proxy` - Comma separated hosts that bypass `proxy`.
+/// * `ca_bundle` - A PEM file of extra certificate authorities to trust.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Download {
+    pub timeout: u64,
+    pub retries: u32,
+    pub max_concurrency: usize,
+    pub token: Option<String>,
+    pub token_hosts: Vec<String>,
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+}
+
+/// Implement the `Default` trait for `Download`.
+impl Default for Download {
+    /// The settings used when the config file does not set them.
+    fn default() -> Self {
+        Download {
+            timeout: 30,
+            retries: 3,
+            max_concurrency: 8,
+            token: None,
+            token_hosts: Vec::new(),
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+        }
+    }
+}
+
+/// Settings for the Git repository of new projects.
+///
+/// ```yaml
+/// git:
+///   initial_branch: main
+///   commit: true
+///   commit_message: Initial commit
+///   remote: git@github.com:{org}/{name}.git
+///   org: sam-kenney
+/// ```
+///
+/// # Fields
+///
+/// * `initial_branch` - The name of the first branch. Defaults to Git's own
+///   default.
+/// * `commit` - Whether to commit every file once the project is created.
+/// * `commit_message` - The message of the initial commit.
+/// * `remote` - The URL of the `origin` remote, where `{name}` is replaced
+///   with the project name and `{org}` with `org`.
+/// * `org` - The organisation or user the remote belongs to.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Git {
+    pub initial_branch: Option<String>,
+    pub commit: bool,
+    pub commit_message: String,
+    pub remote: Option<String>,
+    pub org: Option<String>,
+}
+
+/// Implement the `Default` trait for `Git`.
+impl Default for Git {
+    /// The settings used when the config file does not set them.
+    fn default() -> Self {
+        Git {
+            initial_branch: None,
+            commit: false,
+            commit_message: "Initial commit".to_string(),
+            remote: None,
+            org: None,
+        }
+    }
+}
+
+/// Implement the `Git` struct.
+impl Git {
+    /// Get the URL of the `origin` remote for a project, if one is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The project name.
+    pub fn remote_url(&self, name: &str) -> Result<Option<String>, String> {
+        let remote: &str = match &self.remote {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let url: String = remote.replace("{name}", name);
+        match &self.org {
+            Some(org) => Ok(Some(url.replace("{org}", org))),
+            None if url.contains("{org}") => Err(format!(
+                "The remote `{}` needs an organisation, pass `--org` or set `git.org` in the config",
+                remote
+            )),
+            None => Ok(Some(url)),
+        }
+    }
+}
+
+/// Settings for creating remote repositories with `--create-remote`.
+///
+/// ```yaml
+/// forge:
+///   kind: gitea
+///   api_url: https://git.example.com/api/v1
+///   token: xxxx
+///   org: platform
+///   private: true
+///   protocol: ssh
+/// ```
+///
+/// # Fields
+///
+/// * `kind` - The API the forge speaks.
+/// * `api_url` - The base URL of the API. Defaults to the public GitHub or
+///   GitLab API, and must be set for Gitea.
+/// * `token` - The API token, overridden by the `DEV_CLI_FORGE_TOKEN`
+///   environment variable.
+/// * `org` - The organisation or group to create repositories in, instead
+///   of the token's user.
+/// * `private` - Whether new repositories are private.
+/// * `protocol` - Whether the `origin` remote uses SSH or HTTPS.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Forge {
+    pub kind: ForgeKind,
+    pub api_url: Option<String>,
+    pub token: Option<String>,
+    pub org: Option<String>,
+    pub private: bool,
+    pub protocol: Protocol,
+}
+
+/// Implement the `Default` trait for `Forge`.
+impl Default for Forge {
+    /// The settings used when the config file does not set them.
+    fn default() -> Self {
+        Forge {
+            kind: ForgeKind::Github,
+            api_url: None,
+            token: None,
+            org: None,
+            private: true,
+            protocol: Protocol::Ssh,
+        }
+    }
+}
+
+/// The API a forge speaks.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForgeKind {
+    /// GitHub and GitHub Enterprise.
+    Github,
+    /// Gitea and Forgejo.
+    Gitea,
+    /// GitLab.
+    Gitlab,
+}
+
+/// The protocol a remote is accessed with.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Protocol {
+    /// `git@host:org/name.git`.
+    Ssh,
+    /// `https://host/org/name.git`.
+    Https,
+}
+
+/// A user-defined language.
+///
+/// Either points at a manifest by URL or path, or declares the manifest inline.
+///
+/// ```yaml
+/// languages:
+///   internal-svc:
+///     manifest: https://git.example.com/templates/svc/dev-template.yaml
+///   go:
+///     name: Go
+///     base_url: https://raw.githubusercontent.com/example/go-template/main/
+///     files: [go.mod, main.go]
+///     post: [git-init]
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Language {
+    /// A manifest loaded from a URL or a path relative to the config directory.
+    Manifest { manifest: String },
+    /// A manifest declared inline.
+    Inline(Box<Manifest>),
+}
+
+/// Implement the `Config` struct.
+impl Config {
+    /// Load the config from the config file.
+    ///
+    /// Returns the default config if the file does not exist.
+    pub async fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path: PathBuf = Config::dir();
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents: String = tokio::fs::read_to_string(&path).await?;
+        Config::from_yaml(&contents)
+    }
+
+    /// Load the config from the config file without blocking on a runtime.
+    ///
+    /// Returns the default config if the file does not exist.
+    pub fn load_sync() -> Result<Self, Box<dyn std::error::Error>> {
+        let path: PathBuf = Config::dir();
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        Config::from_yaml(&std::fs::read_to_string(&path)?)
+    }
+
+    /// Load the config, exiting with an error message if it is invalid.
+    pub async fn load_or_exit() -> Self {
+        Config::load().await.unwrap_or_else(|e| {
+            eprintln!("Failed to load config {}: {}", Config::dir().display(), e);
+            std::process::exit(1);
+        })
+    }
+
+    /// Parse the config from YAML.
+    ///
+    /// An empty file is treated as the default config.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The YAML to parse.
+    pub fn from_yaml(contents: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if contents.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        Ok(serde_yaml::from_str(contents)?)
+    }
+
+    /// Get the path to the config file.
+    ///
+    /// The config directory defaults to `~/.dev-config` and can be moved
+    /// with the `DEV_CLI_HOME` environment variable. Tests use a
+    /// `_test_dev_home` directory in the current directory instead, so they
+    /// never touch the real config or cache.
+    pub fn dir() -> PathBuf {
+        let home: PathBuf = match std::env::var_os(HOME_VAR) {
+            Some(home) if !home.is_empty() => PathBuf::from(home),
+            _ if cfg!(test) => PathBuf::from(crate::cli::utils::current_dir()).join(TEST_HOME),
+            _ => dirs::home_dir().unwrap().join(".dev-config"),
+        };
+        home.join("config.yaml")
+    }
+
+    /// Get the directory new projects are created in, if configured.
+    ///
+    /// A leading `~` is expanded to the home directory and relative paths
+    /// are resolved against the directory containing the config file.
+    pub fn projects_root(&self) -> Option<PathBuf> {
+        let root: &str = self.projects_root.as_deref()?;
+        match root.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                Some(dirs::home_dir()?.join(rest.trim_start_matches('/')))
+            }
+            _ => Some(PathBuf::from(Config::resolve(root))),
+        }
+    }
+
+    /// Resolve a manifest location from the config file.
+    ///
+    /// URLs and absolute paths are returned as-is, relative paths are
+    /// resolved against the directory containing the config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - The location as written in the config file.
+    pub fn resolve(location: &str) -> String {
+        if location.contains("://") || PathBuf::from(location).is_absolute() {
+            return location.to_string();
+        }
+        let dir: PathBuf = Config::dir().parent().unwrap().to_path_buf();
+        dir.join(location).to_string_lossy().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_yaml_empty() {
+        let config: Config = Config::from_yaml("").unwrap();
+        assert!(config.languages.is_empty());
+        assert_eq!(config.download, Download::default());
+    }
+
+    #[test]
+    fn test_from_yaml_download() {
+        let config: Config = Config::from_yaml("download:\n  retries: 0\n").unwrap();
+        assert_eq!(config.download.retries, 0);
+        assert_eq!(config.download.timeout, Download::default().timeout);
+    }
+
+    #[test]
+    fn test_projects_root() {
+        let home: PathBuf = dirs::home_dir().unwrap();
+        let config: Config = Config::from_yaml("projects_root: ~/code\n").unwrap();
+        assert_eq!(config.projects_root(), Some(home.join("code")));
+
+        let config: Config = Config::from_yaml("projects_root: /srv/code\n").unwrap();
+        assert_eq!(config.projects_root(), Some(PathBuf::from("/srv/code")));
+
+        let config: Config = Config::from_yaml("projects_root: code\n").unwrap();
+        let dir: PathBuf = Config::dir().parent().unwrap().to_path_buf();
+        assert_eq!(config.projects_root(), Some(dir.join("code")));
+        assert_eq!(Config::default().projects_root(), None);
+    }
+
+    #[test]
+    fn test_remote_url() {
+        let mut git: Git = Config::from_yaml("git:\n  remote: git@github.com:{org}/{name}.git\n")
+            .unwrap()
+            .git;
+        assert_eq!(git.commit_message, "Initial commit");
+        assert!(git.remote_url("my-app").is_err());
+
+        git.org = Some("sam-kenney".to_string());
+        assert_eq!(
+            git.remote_url("my-app").unwrap().unwrap(),
+            "git@github.com:sam-kenney/my-app.git"
+        );
+        assert_eq!(Git::default().remote_url("my-app").unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_yaml_forge() {
+        let config: Config =
+            Config::from_yaml("forge:\n  kind: gitlab\n  protocol: https\n").unwrap();
+        assert_eq!(config.forge.kind, ForgeKind::Gitlab);
+        assert_eq!(config.forge.protocol, Protocol::Https);
+        assert!(config.forge.private);
+    }
+
+    #[test]
+    fn test_from_yaml_ignores_search_keys() {
+        let config: Config = Config::from_yaml("api_key: \"\"\nsearch_engine_id: \"\"").unwrap();
+        assert!(config.languages.is_empty());
+    }
+
+    #[test]
+    fn test_from_yaml_languages() {
+        let config: Config = Config::from_yaml(
+            "languages:
+  internal-svc:
+    manifest: templates/svc.yaml
+  go:
+    name: Go
+    base_url: https://example.com/go/
+    files: [main.go]
+",
+        )
+        .unwrap();
+
+        match &config.languages["internal-svc"] {
+            Language::Manifest { manifest } => assert_eq!(manifest, "templates/svc.yaml"),
+            other => panic!("Expected a manifest reference, got {:?}", other),
+        }
+        match &config.languages["go"] {
+            Language::Inline(manifest) => assert_eq!(manifest.files, vec!["main.go"]),
+            other => panic!("Expected an inline manifest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(Config::resolve("https://a/b.yaml"), "https://a/b.yaml");
+        assert_eq!(Config::resolve("/etc/b.yaml"), "/etc/b.yaml");
+        assert!(Config::resolve("b.yaml").ends_with("_test_dev_home/b.yaml"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Copy template files from a directory on disk.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::template::{self, MANIFEST_FILE};
+
+/// Copy files from a template directory into a new project.
+///
+/// Entries in `files` may be files or directories, directories are copied
+/// recursively. If `files` is empty the whole template directory is copied.
+/// `.git` directories and the template's manifest are never copied.
+///
+/// # Arguments
+///
+/// * `src` - The template directory.
+/// * `files` - The files to copy, relative to `src`.
+/// * `dest` - The project directory, which must not exist.
+pub fn copy_files(
+    src: &Path,
+    files: Vec<&str>,
+    dest: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !src.is_dir() {
+        return Err(format!("Template directory {} does not exist", src.display()).into());
+    }
+
+    fs::create_dir(dest)?;
+
+    if files.is_empty() {
+        return copy_dir(src, dest, Path::new(""));
+    }
+
+    for file in files {
+        let from: PathBuf = src.join(file);
+        let to: PathBuf = dest.join(file);
+        if from.is_dir() {
+            copy_dir(&from, &to, Path::new(file))?;
+        } else {
+            copy_file(&from, &to)
+                .map_err(|e| format!("Failed to copy {}: {}", from.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// List the files `copy_files` would copy, relative to `src`.
+///
+/// # Arguments
+///
+/// * `src` - The template directory.
+/// * `files` - The files to copy, relative to `src`.
+pub fn list_files(src: &Path, files: &[&str]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if !src.is_dir() {
+        return Err(format!("Template directory {} does not exist", src.display()).into());
+    }
+
+    let mut listed: Vec<PathBuf> = Vec::new();
+    if files.is_empty() {
+        list_dir(src, Path::new(""), &mut listed)?;
+    }
+    for file in files {
+        if src.join(file).is_dir() {
+            list_dir(&src.join(file), Path::new(file), &mut listed)?;
+        } else {
+            listed.push(PathBuf::from(file));
+        }
+    }
+    Ok(listed)
+}
+
+/// Recursively list the files in a directory.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to list.
+/// * `rel` - The path of `dir` relative to the template root.
+/// * `listed` - The files listed so far.
+fn list_dir(dir: &Path, rel: &Path, listed: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let rel: PathBuf = rel.join(entry.file_name());
+        if is_ignored(&rel) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            list_dir(&entry.path(), &rel, listed)?;
+        } else {
+            listed.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copy a directory.
+///
+/// # Arguments
+///
+/// * `from` - The directory to copy.
+/// * `to` - The directory to copy into.
+/// * `rel` - The path of `from` relative to the template root.
+fn copy_dir(from: &Path, to: &Path, rel: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry: fs::DirEntry = entry?;
+        let rel: PathBuf = rel.join(entry.file_name());
+        if is_ignored(&rel) {
+            continue;
+        }
+
+        let target: PathBuf = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target, &rel)?;
+        } else {
+            copy_file(&entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy a single file, creating its parent directories.
+///
+/// # Arguments
+///
+/// * `from` - The file to copy.
+/// * `to` - The path to copy the file to.
+fn copy_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to.parent().unwrap())?;
+    fs::copy(from, to)?;
+    Ok(())
+}
+
+/// Mark files in a project as executable.
+///
+/// Does nothing on platforms without Unix permissions. Entries must be
+/// relative paths inside `dir`, and are not followed out of it through
+/// symlinks.
+///
+/// # Arguments
+///
+/// * `dir` - The project directory.
+/// * `files` - The files to mark, relative to `dir`.
+pub fn set_executable(dir: &Path, files: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let root: PathBuf = fs::canonicalize(dir)?;
+    for file in files {
+        if !template::is_inside(file) {
+            return Err(format!("Executable file `{}` is not inside the project", file).into());
+        }
+        let path: PathBuf = dir.join(file);
+        if !path.is_file() {
+            return Err(format!("Executable file `{}` not found in template", file).into());
+        }
+        let path: PathBuf = fs::canonicalize(&path)?;
+        if !path.starts_with(&root) {
+            return Err(format!("Executable file `{}` is not inside the project", file).into());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions: fs::Permissions = fs::metadata(&path)?.permissions();
+            permissions.set_mode(permissions.mode() | 0o111);
+            fs::set_permissions(&path, permissions)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check whether a path relative to the template root should be skipped.
+///
+/// # Arguments
+///
+/// * `rel` - The path relative to the template root.
+fn is_ignored(rel: &Path) -> bool {
+    rel == Path::new(MANIFEST_FILE) || rel.components().any(|c| c.as_os_str() == ".git")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a template directory to copy from.
+    fn make_template(dir: &str) {
+        fs::create_dir_all(format!("{}/src/nested", dir)).unwrap();
+        fs::create_dir_all(format!("{}/.git", dir)).unwrap();
+        fs::write(format!("{}/README.md", dir), "readme").unwrap();
+        fs::write(format!("{}/src/main.py", dir), "main").unwrap();
+        fs::write(format!("{}/src/nested/mod.py", dir), "mod").unwrap();
+        fs::write(format!("{}/.git/HEAD", dir), "head").unwrap();
+        fs::write(format!("{}/{}", dir, MANIFEST_FILE), "name: T").unwrap();
+    }
+
+    #[test]
+    fn test_copy_files_all() {
+        make_template("_test_copy_all_src");
+        let name: String = "_test_copy_all".to_string();
+        copy_files(Path::new("_test_copy_all_src"), vec![], Path::new(&name)).unwrap();
+
+        assert_eq!(
+            fs::read_to_string("_test_copy_all/README.md").unwrap(),
+            "readme"
+        );
+        assert_eq!(
+            fs::read_to_string("_test_copy_all/src/nested/mod.py").unwrap(),
+            "mod"
+        );
+        assert!(!Path::new("_test_copy_all/.git").exists());
+        assert!(!Path::new("_test_copy_all").join(MANIFEST_FILE).exists());
+
+        fs::remove_dir_all("_test_copy_all_src").unwrap();
+        fs::remove_dir_all(name).unwrap();
+    }
+
+    #[test]
+    fn test_copy_files_include_list() {
+        make_template("_test_copy_list_src");
+        let name: String = "_test_copy_list".to_string();
+        copy_files(
+            Path::new("_test_copy_list_src"),
+            vec!["src"],
+            Path::new(&name),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string("_test_copy_list/src/main.py").unwrap(),
+            "main"
+        );
+        assert_eq!(
+            fs::read_to_string("_test_copy_list/src/nested/mod.py").unwrap(),
+            "mod"
+        );
+        assert!(!Path::new("_test_copy_list/README.md").exists());
+
+        fs::remove_dir_all("_test_copy_list_src").unwrap();
+        fs::remove_dir_all(name).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir: &Path = Path::new("_test_set_executable");
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("gradlew"), "#!/bin/sh").unwrap();
+        set_executable(dir, &["gradlew".to_string()]).unwrap();
+        let mode: u32 = fs::metadata(dir.join("gradlew"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o111, 0o111);
+        assert!(set_executable(dir, &["missing".to_string()]).is_err());
+
+        fs::write("_test_set_executable_outside", "secret").unwrap();
+        let outside: PathBuf = fs::canonicalize("_test_set_executable_outside").unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+        for file in ["../_test_set_executable_outside", "/etc/passwd", "link"] {
+            let error: String = set_executable(dir, &[file.to_string()])
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                error,
+                format!("Executable file `{}` is not inside the project", file)
+            );
+        }
+        let mode: u32 = fs::metadata(&outside).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0);
+        fs::remove_file(outside).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_files() {
+        make_template("_test_list_files_src");
+        let src: &Path = Path::new("_test_list_files_src");
+
+        assert_eq!(
+            list_files(src, &[]).unwrap(),
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("src/main.py"),
+                PathBuf::from("src/nested/mod.py"),
+            ]
+        );
+        assert_eq!(
+            list_files(src, &["README.md"]).unwrap(),
+            vec![PathBuf::from("README.md")]
+        );
+
+        fs::remove_dir_all(src).unwrap();
+    }
+}
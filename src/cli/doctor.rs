@@ -0,0 +1,369 @@
+//! Check the external tools templates need are installed.
+use std::collections::BTreeMap;
+use std::process::{Command, Output};
+
+use crate::cli::bash;
+use crate::cli::cache::Mode;
+use crate::cli::config::Config;
+use crate::cli::template::{registry, Manifest};
+
+/// A program templates may need.
+///
+/// # Fields
+///
+/// * `program` - The name templates refer to it by.
+/// * `candidates` - The executables to try, in order.
+/// * `minimum` - The oldest supported version.
+/// * `hint` - How to install it.
+struct Tool {
+    program: &'static str,
+    candidates: &'static [&'static str],
+    minimum: Option<&'static str>,
+    hint: &'static str,
+}
+
+/// The programs used by the built-in templates.
+const TOOLS: [Tool; 8] = [
+    Tool {
+        program: "git",
+        candidates: &["git"],
+        minimum: Some("2.28"),
+        hint: "Install Git from https://git-scm.com/downloads",
+    },
+    Tool {
+        program: "python3",
+        candidates: &["python3"],
+        minimum: Some("3.8"),
+        hint: "Install Python 3 from https://www.python.org/downloads/",
+    },
+    Tool {
+        program: "poetry",
+        candidates: &["poetry"],
+        minimum: Some("1.2"),
+        hint: "Install Poetry with `pipx install poetry`",
+    },
+    Tool {
+        program: "cargo",
+        candidates: &["cargo"],
+        minimum: Some("1.56"),
+        hint: "Install Rust with rustup from https://rustup.rs",
+    },
+    Tool {
+        program: "ruby",
+        candidates: &["ruby"],
+        minimum: Some("2.7"),
+        hint: "Install Ruby from https://www.ruby-lang.org/en/documentation/installation/",
+    },
+    Tool {
+        program: "bundle",
+        candidates: &["bundle"],
+        minimum: Some("2.0"),
+        hint: "Install Bundler with `gem install bundler`",
+    },
+    Tool {
+        program: "make",
+        candidates: &["make"],
+        minimum: None,
+        hint: "Install make, e.g. `apt install make` or `xcode-select --install`",
+    },
+    Tool {
+        program: "c++",
+        candidates: &["c++", "g++", "clang++"],
+        minimum: None,
+        hint: "Install a C++ compiler such as g++ or clang++",
+    },
+];
+
+/// The result of checking a program.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The program works and is new enough.
+    Ok,
+    /// No executable was found.
+    Missing,
+    /// The version is older than the minimum.
+    Outdated(String),
+    /// The executable was found but does not work.
+    Broken(String),
+}
+
+/// A checked program.
+///
+/// # Fields
+///
+/// * `program` - The name templates refer to it by.
+/// * `version` - The version found, if it could be read.
+/// * `status` - Whether the program can be used.
+/// * `hint` - How to install it, for known programs.
+#[derive(Debug)]
+pub struct Check {
+    pub program: String,
+    pub version: Option<String>,
+    pub status: Status,
+    pub hint: Option<&'static str>,
+}
+
+/// Implement the `Check` struct.
+impl Check {
+    /// Check whether the program can be used.
+    pub fn is_ok(&self) -> bool {
+        self.status == Status::Ok
+    }
+
+    /// Describe a problem with the program and how to fix it.
+    pub fn message(&self) -> String {
+        match self.hint {
+            Some(hint) if !self.is_ok() => format!("{}\n    {}", self.problem(), hint),
+            _ => self.problem(),
+        }
+    }
+
+    /// Describe a problem with the program.
+    fn problem(&self) -> String {
+        match &self.status {
+            Status::Ok => format!("`{}` is installed", self.program),
+            Status::Missing => format!("`{}` was not found", self.program),
+            Status::Outdated(minimum) => format!(
+                "`{}` {} is older than the minimum supported version {}",
+                self.program,
+                self.version.as_deref().unwrap_or_default(),
+                minimum
+            ),
+            Status::Broken(reason) => format!("`{}` does not work: {}", self.program, reason),
+        }
+    }
+
+    /// Print the check to the console.
+    ///
+    /// # Arguments
+    ///
+    /// * `used_by` - The languages that need the program.
+    pub fn print(&self, used_by: &[String]) {
+        let version: &str = self.version.as_deref().unwrap_or("unknown version");
+        match self.status {
+            Status::Ok => println!("✓ {} {} ({})", self.program, version, used_by.join(", ")),
+            _ => {
+                println!("✗ {} ({})", self.problem(), used_by.join(", "));
+                if let Some(hint) = self.hint {
+                    println!("    {}", hint);
+                }
+            }
+        }
+    }
+}
+
+/// Check a program is installed, works and is new enough.
+///
+/// # Arguments
+///
+/// * `program` - The program, e.g. `git`.
+pub fn check(program: &str) -> Check {
+    let tool: Option<&Tool> = TOOLS.iter().find(|tool| tool.program == program);
+    let candidates: Vec<&str> = match tool {
+        Some(tool) => tool.candidates.to_vec(),
+        None => vec![program],
+    };
+    let mut check: Check = Check {
+        program: program.to_string(),
+        version: None,
+        status: Status::Missing,
+        hint: tool.map(|tool| tool.hint),
+    };
+
+    let output: Output = match candidates
+        .iter()
+        .find_map(|candidate| Command::new(candidate).arg("--version").output().ok())
+    {
+        Some(output) => output,
+        None => return check,
+    };
+    let text: String = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if !output.status.success() {
+        let reason: &str = text.lines().next().unwrap_or_default().trim();
+        check.status = Status::Broken(format!("`{} --version` failed: {}", program, reason));
+        return check;
+    }
+
+    check.version = parse_version(&text);
+    check.status = match (tool.and_then(|tool| tool.minimum), &check.version) {
+        (Some(minimum), Some(version)) if is_older(version, minimum) => {
+            Status::Outdated(minimum.to_string())
+        }
+        _ if program == "python3" && !has_venv() => Status::Broken(
+            "the venv module is not available, e.g. install `python3-venv`".to_string(),
+        ),
+        _ => Status::Ok,
+    };
+    check
+}
+
+/// Check whether Python can create virtual environments.
+fn has_venv() -> bool {
+    Command::new("python3")
+        .arg("-c")
+        .arg("import ensurepip, venv")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Find the first version number in a program's output.
+///
+/// # Arguments
+///
+/// * `text` - The output of `--version`, e.g. `git version 2.39.5`.
+fn parse_version(text: &str) -> Option<String> {
+    let regex: regex::Regex = regex::Regex::new(r"\d+(\.\d+)+").unwrap();
+    regex.find(text).map(|version| version.as_str().to_string())
+}
+
+/// Compare dotted version numbers.
+///
+/// # Arguments
+///
+/// * `version` - The version found, e.g. `2.39.5`.
+/// * `minimum` - The oldest supported version, e.g. `2.28`.
+fn is_older(version: &str, minimum: &str) -> bool {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or_default())
+            .collect()
+    };
+    parts(version) < parts(minimum)
+}
+
+/// Check the tools needed by every registered language.
+///
+/// Returns whether every tool can be used.
+///
+/// # Arguments
+///
+/// * `config` - The loaded config.
+pub async fn run(config: &Config) -> bool {
+    let mut programs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for key in registry::keys(config) {
+        let manifest: Manifest = match registry::load(&key, config, Mode::Offline).await {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Skipping `{}`: {}", key, e);
+                continue;
+            }
+        };
+        for program in manifest.toolchain() {
+            programs.entry(program).or_default().push(key.clone());
+        }
+    }
+
+    let mut ok: bool = true;
+    for (program, used_by) in &programs {
+        let check: Check = check(program);
+        check.print(used_by);
+        ok &= check.is_ok();
+    }
+    ok
+}
+
+/// Check the tools a template needs before creating a project.
+///
+/// Programs run while generating the project must work, and so must an
+/// initial commit if one is made. Programs the template only lists in
+/// `requires` produce a warning.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+/// * `commit` - Whether an initial commit will be made.
+pub fn preflight(manifest: &Manifest, commit: bool) -> Result<(), String> {
+    let programs: Vec<String> = manifest.programs();
+    let mut problems: Vec<String> = programs
+        .iter()
+        .map(|program| check(program))
+        .filter(|check| !check.is_ok())
+        .map(|check| format!("  {}", check.message()))
+        .collect();
+    if commit && bash::git_config("user.email").is_none() {
+        problems.push(
+            "  Git `user.email` is not set, so the initial commit would fail. Set it with \
+             `git config --global user.email <email>` or pass `--no-commit`"
+                .to_string(),
+        );
+    }
+
+    for program in manifest.toolchain() {
+        if programs.contains(&program) {
+            continue;
+        }
+        let check: Check = check(&program);
+        if !check.is_ok() {
+            eprintln!("Warning: {}", check.message());
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Cannot create a {} project, run `dev doctor` for details:\n{}",
+            manifest.name,
+            problems.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("git version 2.39.5"),
+            Some("2.39.5".to_string())
+        );
+        assert_eq!(
+            parse_version("Poetry (version 1.8.2)"),
+            Some("1.8.2".to_string())
+        );
+        assert_eq!(
+            parse_version("ruby 3.2.2p53 (2023-03-30)"),
+            Some("3.2.2".to_string())
+        );
+        assert_eq!(parse_version("no version here"), None);
+    }
+
+    #[test]
+    fn test_is_older() {
+        assert!(is_older("2.25.1", "2.28"));
+        assert!(!is_older("2.28", "2.28"));
+        assert!(!is_older("2.39.5", "2.28"));
+        assert!(!is_older("10.0", "9.1"));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(check("git").is_ok());
+
+        let missing: Check = check("dev-cli-missing-tool");
+        assert_eq!(missing.status, Status::Missing);
+        assert!(missing.message().contains("was not found"));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let manifest: Manifest =
+            Manifest::from_yaml("name: Test\npost: [git-init]\nrequires: [dev-cli-missing-tool]\n")
+                .unwrap();
+        assert!(preflight(&manifest, false).is_ok());
+
+        let error: Option<String> = preflight(&manifest, true).err();
+        assert_eq!(error.is_some(), bash::git_config("user.email").is_none());
+        if let Some(error) = error {
+            assert!(error.contains("Git `user.email` is not set"));
+        }
+    }
+}
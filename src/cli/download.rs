@@ -1,40 +1,159 @@
 use std::fs::{self, File};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::cli::config::{Config, Download};
+use crate::cli::progress;
+use crate::cli::template;
+
+/// The environment variable that overrides the configured token.
+const TOKEN_VAR: &str = "DEV_CLI_TOKEN";
+
+/// The longest a `Retry-After` header is honoured for.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// A file that could not be downloaded.
+///
+/// # Fields
+///
+/// * `url` - The URL of the file.
+/// * `reason` - Why the download failed.
+#[derive(Debug)]
+pub struct FailedDownload {
+    pub url: String,
+    pub reason: String,
+}
+
+/// The files that could not be downloaded by `download_files`.
+#[derive(Debug)]
+pub struct DownloadError(pub Vec<FailedDownload>);
+
+/// Implement the `Display` trait for `DownloadError`.
+impl std::fmt::Display for DownloadError {
+    /// List every file that failed and why.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to download {} file(s):", self.0.len())?;
+        for failed in &self.0 {
+            write!(f, "\n    {}: {}", failed.url, failed.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Implement the `Error` trait for `DownloadError`.
+impl std::error::Error for DownloadError {}
 
 /// Download files from a URL.
 ///
+/// Every file is attempted, at most `max_concurrency` at once, and if any
+/// fail the error lists all of them. Nothing is downloaded if a file's path
+/// would leave `dir`.
+///
 /// # Arguments
 ///
 /// * `base_url` - The base URL to download the files from.
 /// * `files` - The files to download.
-/// * `name` - The name of the project.
-pub async fn download_files(base_url: &str, files: Vec<&str>, name: &String) {
+/// * `dir` - The directory to download the files into, relative to the
+///   current directory.
+pub async fn download_files(
+    base_url: &str,
+    files: Vec<&str>,
+    dir: impl AsRef<Path>,
+) -> Result<(), DownloadError> {
     use crate::cli::utils;
-    let dir: String = format!("{}/{}", utils::current_dir(), name);
+    let dir: PathBuf = Path::new(&utils::current_dir()).join(dir);
+
+    let outside: Vec<FailedDownload> = files
+        .iter()
+        .filter(|file| !template::is_inside(file))
+        .map(|file| FailedDownload {
+            url: format!("{}{}", base_url, file),
+            reason: format!("`{}` is not a path inside the template", file),
+        })
+        .collect();
+    if !outside.is_empty() {
+        return Err(DownloadError(outside));
+    }
+
+    mkdir_if_not_exists(dir.to_string_lossy().to_string())
+        .await
+        .map_err(|e| {
+            DownloadError(vec![FailedDownload {
+                url: base_url.to_string(),
+                reason: e.to_string(),
+            }])
+        })?;
 
-    mkdir_if_not_exists(dir).await;
+    let client: reqwest::Client = client().map_err(|e| {
+        DownloadError(vec![FailedDownload {
+            url: base_url.to_string(),
+            reason: e.to_string(),
+        }])
+    })?;
 
+    progress::start_files(files.len());
     let tasks = files.iter().map(|file| {
         let url = format!("{}{}", base_url, file);
-        let path = format!("{}/{}/{}", utils::current_dir(), name, file);
-        download_file(url, path)
+        let path = dir.join(file).to_string_lossy().to_string();
+        let client = &client;
+        async move {
+            match download_file(client, url.clone(), path).await {
+                Ok(bytes) => {
+                    progress::file_done(file, bytes);
+                    Ok(())
+                }
+                Err(e) => Err(FailedDownload {
+                    url,
+                    reason: e.to_string(),
+                }),
+            }
+        }
     });
 
-    futures::future::join_all(tasks).await;
+    let failed: Vec<FailedDownload> = futures::stream::iter(tasks)
+        .buffer_unordered(settings().max_concurrency.max(1))
+        .filter_map(|result| async move { result.err() })
+        .collect()
+        .await;
+    progress::finish_files();
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(DownloadError(failed))
+    }
 }
 
 /// Download a file from a URL.
 ///
+/// Non-2xx responses are treated as errors, and the body is streamed to
+/// disk unchanged so binary files are not corrupted. Returns the number of
+/// bytes written.
+///
 /// # Arguments
 ///
+/// * `client` - The client to send the request with.
 /// * `url` - The URL to download the file from.
 /// * `path` - The path to save the file to.
-async fn download_file(url: String, path: String) -> Result<(), Box<dyn std::error::Error>> {
-    let resp = reqwest::get(url).await?.text().await?;
-    fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+async fn download_file(
+    client: &reqwest::Client,
+    url: String,
+    path: String,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut resp = get(client, &url, settings().retries).await?;
+    fs::create_dir_all(Path::new(&path).parent().unwrap())?;
     let mut out = File::create(path)?;
-    std::io::copy(&mut resp.as_bytes(), &mut out)?;
-    Ok(())
+    let mut bytes: u64 = 0;
+    while let Some(chunk) = resp.chunk().await? {
+        out.write_all(&chunk)?;
+        bytes += chunk.len() as u64;
+    }
+    Ok(bytes)
 }
 
 /// Download text from a URL.
@@ -42,26 +161,221 @@ async fn download_file(url: String, path: String) -> Result<(), Box<dyn std::err
 /// # Arguments
 ///
 /// * `url` - The URL to download the text from.
+/// * `retry` - Whether to retry transient failures, which is not worth the
+///   wait for optional downloads.
+pub async fn download_text(url: String, retry: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let retries: u32 = if retry { settings().retries } else { 0 };
+    let resp = get(&client()?, &url, retries).await?.text().await?;
+    Ok(resp)
+}
+
+/// Download the raw bytes of a URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL to download.
+pub async fn download_bytes(url: String) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let resp = get(&client()?, &url, settings().retries)
+        .await?
+        .bytes()
+        .await?;
+    Ok(resp.to_vec())
+}
+
+/// Download JSON from a URL and deserialize it.
+///
+/// # Arguments
+///
+/// * `url` - The URL to download the JSON from.
 pub async fn download_to<T>(url: String) -> Result<T, Box<dyn std::error::Error>>
 where
     T: serde::de::DeserializeOwned,
 {
-    let resp = reqwest::get(url).await?.json::<T>().await?;
+    let resp = get(&client()?, &url, settings().retries)
+        .await?
+        .json::<T>()
+        .await?;
     Ok(resp)
 }
 
-/// Create a directory if it does not exist.
+/// Send a GET request, retrying transient failures.
+///
+/// The bearer token is sent if the URL's host is one of `token_hosts`.
+/// Timeouts, connection errors, `429` and `5xx` responses are retried up to
+/// `retries` times with exponential backoff, waiting for `Retry-After` when
+/// the server sends it. Any other non-2xx response is an error.
+///
+/// # Arguments
+///
+/// * `client` - The client to send the request with.
+/// * `url` - The URL to request.
+/// * `retries` - How many times to retry, usually the configured `retries`.
+async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    retries: u32,
+) -> reqwest::Result<reqwest::Response> {
+    let token: Option<String> = token().filter(|_| sends_token(url, &settings().token_hosts));
+    warn_unused_token();
+    let mut attempt: u32 = 0;
+    loop {
+        let mut request: reqwest::RequestBuilder = client.get(url);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        let wait: Duration = match request.send().await {
+            Ok(resp) if attempt < retries && is_transient(resp.status()) => {
+                retry_after(&resp).unwrap_or_else(|| backoff(attempt))
+            }
+            Ok(resp) => return resp.error_for_status(),
+            Err(e) if attempt < retries && (e.is_timeout() || e.is_connect()) => backoff(attempt),
+            Err(e) => return Err(e),
+        };
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// Build a client using the download settings.
+///
+/// Applies the timeout, proxy and extra certificate authorities.
+pub fn client() -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+    let settings: &Download = settings();
+    let mut builder: reqwest::ClientBuilder =
+        reqwest::Client::builder().timeout(Duration::from_secs(settings.timeout));
+
+    if let Some(proxy) = &settings.proxy {
+        let no_proxy: Option<reqwest::NoProxy> = match &settings.no_proxy {
+            Some(hosts) => reqwest::NoProxy::from_string(hosts),
+            None => reqwest::NoProxy::from_env(),
+        };
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?.no_proxy(no_proxy));
+    }
+
+    if let Some(path) = &settings.ca_bundle {
+        let pem: Vec<u8> =
+            fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path, e))?;
+        for cert in pem_certificates(&pem) {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&cert)?);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Split a PEM bundle into its certificates.
+///
+/// # Arguments
+///
+/// * `pem` - The contents of the bundle.
+fn pem_certificates(pem: &[u8]) -> Vec<Vec<u8>> {
+    const END: &str = "-----END CERTIFICATE-----";
+    String::from_utf8_lossy(pem)
+        .split_inclusive(END)
+        .filter(|cert| cert.contains(END))
+        .map(|cert| cert.trim().as_bytes().to_vec())
+        .collect()
+}
+
+/// Get the bearer token from `DEV_CLI_TOKEN` or the config file.
+fn token() -> Option<String> {
+    std::env::var(TOKEN_VAR)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or_else(|| settings().token.clone())
+}
+
+/// Warn, once, that a token is set but never sent because `token_hosts` is
+/// empty.
+fn warn_unused_token() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    if settings().token_hosts.is_empty() && token().is_some() {
+        WARNED.call_once(|| {
+            eprintln!(
+                "Warning: a download token is set but `download.token_hosts` is empty, \
+                 so it is not sent to any host"
+            )
+        });
+    }
+}
+
+/// Check whether the token should be sent to a URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the request.
+/// * `hosts` - The hosts, and their subdomains, the token is sent to.
+fn sends_token(url: &str, hosts: &[String]) -> bool {
+    let host: String = match reqwest::Url::parse(url) {
+        Ok(url) => url.host_str().unwrap_or_default().to_string(),
+        Err(_) => return false,
+    };
+    hosts
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
+/// Get the download settings from the config file.
+///
+/// The config is read once, and defaults are used if it cannot be read.
+fn settings() -> &'static Download {
+    static SETTINGS: OnceLock<Download> = OnceLock::new();
+    SETTINGS.get_or_init(|| {
+        Config::load_sync()
+            .map(|config| config.download)
+            .unwrap_or_default()
+    })
+}
+
+/// Check whether a response status is worth retrying.
+///
+/// # Arguments
+///
+/// * `status` - The response status.
+fn is_transient(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Get how long a response asks to wait before retrying.
+///
+/// Only the delay-seconds form of `Retry-After` is supported.
+///
+/// # Arguments
+///
+/// * `resp` - The response.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let seconds: u64 = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Get how long to wait before a retry, doubling from half a second.
+///
+/// # Arguments
+///
+/// * `attempt` - The number of attempts already made, starting at 0.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500 * 2u64.pow(attempt.min(6)))
+}
+
+/// Create a directory, failing if it already exists.
 ///
 /// # Arguments
 ///
 /// * `dir` - The directory to create.
-async fn mkdir_if_not_exists(dir: String) {
-    if !Path::new(&dir).exists() {
-        tokio::fs::create_dir(dir).await.unwrap();
-        return;
+pub async fn mkdir_if_not_exists(dir: String) -> std::io::Result<()> {
+    if Path::new(&dir).exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Directory {} already exists", dir),
+        ));
     }
-    eprintln!("Directory {} already exists", dir);
-    std::process::exit(1)
+    tokio::fs::create_dir(dir).await
 }
 
 #[cfg(test)]
@@ -78,7 +392,7 @@ mod tests {
             "{}/_test_download_file/.gitignore",
             std::env::current_dir().unwrap().to_str().unwrap()
         );
-        download_file(url, path).await.unwrap();
+        download_file(&client().unwrap(), url, path).await.unwrap();
         let mut file = File::open("_test_download_file/.gitignore").unwrap();
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
@@ -92,8 +406,9 @@ mod tests {
             "{}/_test_mkdir_if_not_exists",
             std::env::current_dir().unwrap().to_str().unwrap()
         );
-        mkdir_if_not_exists(dir.clone()).await;
+        mkdir_if_not_exists(dir.clone()).await.unwrap();
         assert!(Path::new(&dir).exists());
+        assert!(mkdir_if_not_exists(dir.clone()).await.is_err());
         fs::remove_dir(dir).unwrap();
     }
 
@@ -103,11 +418,65 @@ mod tests {
             "https://raw.githubusercontent.com/sam-kenney/dev-cli/main/".to_string();
         let files: Vec<&str> = vec![".gitignore"];
         let name: String = "_test_download_files".to_string();
-        download_files(&base_url, files, &name).await;
+        download_files(&base_url, files, &name).await.unwrap();
         let mut file = File::open("_test_download_files/.gitignore").unwrap();
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
         assert_eq!(contents, "/target\n");
         fs::remove_dir_all("_test_download_files").unwrap();
     }
+
+    #[tokio::test]
+    async fn test_download_files_reports_failures() {
+        let base_url: String = "http://127.0.0.1:9/".to_string();
+        let files: Vec<&str> = vec!["a.txt", "b.txt"];
+        let name: String = "_test_download_files_failures".to_string();
+        let err: DownloadError = download_files(&base_url, files, &name).await.unwrap_err();
+        assert_eq!(err.0.len(), 2);
+        assert!(err.to_string().contains("http://127.0.0.1:9/b.txt"));
+        fs::remove_dir_all(name).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_files_outside_dir() {
+        let base_url: String = "http://127.0.0.1:9/".to_string();
+        let files: Vec<&str> = vec!["a.txt", "/tmp/dev-cli-escape.txt", "../escape.txt"];
+        let name: String = "_test_download_files_outside".to_string();
+        let err: DownloadError = download_files(&base_url, files, &name).await.unwrap_err();
+        assert_eq!(err.0.len(), 2);
+        assert!(err.to_string().contains("`../escape.txt` is not a path"));
+        assert!(!Path::new(&name).exists());
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(100), backoff(6));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(!is_transient(reqwest::StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_sends_token() {
+        let hosts: Vec<String> = vec!["example.com".to_string()];
+        assert!(sends_token("https://example.com/t/a.txt", &hosts));
+        assert!(sends_token("https://git.example.com/t/a.txt", &hosts));
+        assert!(!sends_token("https://badexample.com/a.txt", &hosts));
+        assert!(!sends_token("https://example.com/a.txt", &[]));
+    }
+
+    #[test]
+    fn test_pem_certificates() {
+        let pem: &str = "-----BEGIN CERTIFICATE-----\nAAA\n-----END CERTIFICATE-----\n\
+                         -----BEGIN CERTIFICATE-----\nBBB\n-----END CERTIFICATE-----\n";
+        let certs: Vec<Vec<u8>> = pem_certificates(pem.as_bytes());
+        assert_eq!(certs.len(), 2);
+        assert!(String::from_utf8_lossy(&certs[1]).contains("BBB"));
+    }
 }
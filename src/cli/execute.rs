@@ -1,27 +1,952 @@
-use crate::cli::lang;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::cli::cache::Mode;
+use crate::cli::config::{Config, Git};
+use crate::cli::merge::{self, Conflict, Summary};
+use crate::cli::template::questions::{self, Answers, ANSWERS_FILE};
+use crate::cli::template::render::{self, Variables};
+use crate::cli::template::{self, registry, Manifest, Source, Step};
+use crate::cli::template::{conditions, naming};
+use crate::cli::utils::TempDir;
+use crate::cli::{archive, bash, cache, copy, doctor, forge, progress, prompt, utils};
+
+/// Options for creating a project.
+///
+/// # Fields
+///
+/// * `name` - The name of the project.
+/// * `lang` - The language key to generate the project for.
+/// * `template` - A template directory, archive URL or Git URL to use
+///   instead of a registered language.
+/// * `reference` - The branch, tag or commit to use for a Git template.
+/// * `vars` - `key=value` template variables.
+/// * `offline` - Only use cached templates, never the network.
+/// * `refresh` - Fetch fresh copies of cached templates.
+/// * `path` - The directory to create the project in instead of `name`
+///   under the configured projects root.
+/// * `into` - An existing directory to generate into.
+/// * `force` - Generate into the project directory even if it already exists.
+/// * `conflict` - What to do with files that already exist.
+/// * `dry_run` - Print what would be done without creating anything.
+/// * `no_hooks` - Skip the template's hooks.
+/// * `answers` - A YAML file of answers to the template's questions.
+/// * `no_git` - Skip initialising a Git repository.
+/// * `branch` - The initial branch, overriding the config.
+/// * `commit` - Whether to create an initial commit, overriding the config.
+/// * `message` - The initial commit message, overriding the config.
+/// * `remote` - The `origin` remote URL pattern, overriding the config.
+/// * `org` - The organisation in the remote URL and of a created remote
+///   repository, overriding the config.
+/// * `create_remote` - Create the remote repository on the forge and push.
+/// * `private` - Whether a created remote repository is private,
+///   overriding the config.
+/// * `description` - The description of a created remote repository.
+pub struct Options {
+    pub name: String,
+    pub lang: String,
+    pub template: Option<String>,
+    pub reference: Option<String>,
+    pub vars: Vec<String>,
+    pub offline: bool,
+    pub refresh: bool,
+    pub path: Option<String>,
+    pub into: Option<String>,
+    pub force: bool,
+    pub conflict: Conflict,
+    pub dry_run: bool,
+    pub no_hooks: bool,
+    pub answers: Option<String>,
+    pub no_git: bool,
+    pub branch: Option<String>,
+    pub commit: Option<bool>,
+    pub message: Option<String>,
+    pub remote: Option<String>,
+    pub org: Option<String>,
+    pub create_remote: bool,
+    pub private: Option<bool>,
+    pub description: Option<String>,
+}
+
+/// The Git repository to set up in a new project.
+///
+/// # Fields
+///
+/// * `branch` - The initial branch, if not Git's default.
+/// * `remote` - The URL of the `origin` remote.
+/// * `commit` - The message of the initial commit, if one is made.
+/// * `forge` - The remote repository to create and push to.
+#[derive(Clone)]
+struct Repo {
+    branch: Option<String>,
+    remote: Option<String>,
+    commit: Option<String>,
+    forge: Option<forge::Request>,
+}
 
 /// Execute the project.
-pub async fn execute(name: String, lang: String) {
-    match lang.as_str() {
-        "cpp" => {
-            lang::cpp(name).await;
-            std::process::exit(0);
+///
+/// Ctrl-C stops generation and removes anything it created.
+///
+/// # Arguments
+///
+/// * `options` - The options for the project.
+pub async fn execute(options: Options) {
+    if let Err(e) = run(options, tokio::signal::ctrl_c()).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Create the project, removing anything created if `interrupt` completes
+/// first.
+///
+/// `interrupt` is polled before any work starts, so it is listening for
+/// the whole of creation.
+///
+/// # Arguments
+///
+/// * `options` - The options for the project.
+/// * `interrupt` - Completes when creation should stop, e.g. on Ctrl-C.
+async fn run<F: Future>(options: Options, interrupt: F) -> Result<(), Box<dyn std::error::Error>> {
+    let rollback: Rollback = Rollback::default();
+    tokio::select! {
+        biased;
+        _ = interrupt => {
+            rollback.run();
+            Err("Interrupted, the project was not created".into())
+        }
+        result = create(options, &rollback) => result,
+    }
+}
+
+/// The directories being created, removed if creation is interrupted.
+#[derive(Default)]
+struct Rollback(Mutex<Vec<PathBuf>>);
+
+/// Implement the `Rollback` struct.
+impl Rollback {
+    /// Remove a directory if creation is interrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory.
+    fn track(&self, path: &Path) {
+        self.0.lock().unwrap().push(path.to_path_buf());
+    }
+
+    /// Stop removing a directory, e.g. once it has been kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory.
+    fn forget(&self, path: &Path) {
+        self.0.lock().unwrap().retain(|tracked| tracked != path);
+    }
+
+    /// Remove every tracked directory.
+    fn run(&self) {
+        for path in self.0.lock().unwrap().drain(..) {
+            let _ = fs::remove_dir_all(path);
+        }
+    }
+}
+
+/// Run blocking work, such as a prompt or an external command, on another
+/// thread so it does not stop Ctrl-C from being handled.
+///
+/// # Arguments
+///
+/// * `work` - The work to run.
+async fn blocking<T, F>(work: F) -> Result<T, Box<dyn std::error::Error>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    Ok(tokio::task::spawn_blocking(work).await??)
+}
+
+/// Create the project.
+///
+/// The project is generated, and its relocatable post steps run, in a
+/// staging directory next to the target, so nothing is left behind if
+/// generation fails. It is then renamed into place, or merged file by file
+/// if the target already exists. Remaining steps run in place, and a new
+/// project is removed again if one of them fails.
+///
+/// # Arguments
+///
+/// * `options` - The options for the project.
+/// * `rollback` - Tracks the directories to remove if creation is
+///   interrupted.
+async fn create(options: Options, rollback: &Rollback) -> Result<(), Box<dyn std::error::Error>> {
+    let name: String = options.name;
+    naming::validate_name(&name)?;
+    let mut vars: Variables = render::variables(&name, &options.vars)?;
+
+    let mode: Mode = Mode::new(options.offline, options.refresh);
+    let config: Config = Config::load()
+        .await
+        .map_err(|e| format!("Failed to load config {}: {}", Config::dir().display(), e))?;
+
+    let target: PathBuf = match options.into.as_ref().or(options.path.as_ref()) {
+        Some(dir) => PathBuf::from(dir),
+        None => config.projects_root().unwrap_or_default().join(&name),
+    };
+    let merging: bool = target.exists();
+    if merging && options.into.is_none() && !options.force {
+        return Err(format!(
+            "Directory {} already exists, use `--force` or `--into` to generate into it",
+            target.display()
+        )
+        .into());
+    }
+    if merging && !target.is_dir() {
+        return Err(format!("{} is not a directory", target.display()).into());
+    }
+    let absolute: PathBuf = if merging {
+        fs::canonicalize(&target)?
+    } else {
+        Path::new(&utils::current_dir()).join(&target)
+    };
+    let parent: &Path = absolute
+        .parent()
+        .ok_or("Cannot generate into the filesystem root")?;
+
+    let had_repo: bool = target.join(".git").exists();
+    let git_flag: Option<&str> = [
+        (options.create_remote, "--create-remote"),
+        (options.branch.is_some(), "--branch"),
+        (options.remote.is_some(), "--remote"),
+        (options.org.is_some(), "--org"),
+        (options.message.is_some(), "--message"),
+        (options.commit == Some(true), "--commit"),
+    ]
+    .into_iter()
+    .find_map(|(given, flag)| given.then_some(flag));
+    let repo: Option<Repo> = match options.no_git {
+        true => None,
+        false => {
+            let forge: Option<forge::Request> = match options.create_remote {
+                true => Some(forge::Request::new(
+                    &config.forge,
+                    &name,
+                    options.org.clone(),
+                    options.description,
+                    options.private,
+                )?),
+                false => None,
+            };
+            let mut git: Git = config.git.clone();
+            git.initial_branch = options.branch.or(git.initial_branch);
+            git.remote = options.remote.or(git.remote);
+            git.org = options.org.or(git.org);
+            if let Some(message) = options.message {
+                git.commit = true;
+                git.commit_message = message;
+            }
+            git.commit = options.commit.unwrap_or(git.commit) || forge.is_some();
+            Some(Repo {
+                remote: git.remote_url(&name)?,
+                branch: git.initial_branch,
+                commit: git.commit.then_some(git.commit_message),
+                forge,
+            })
+        }
+    };
+
+    let source: Option<String> = options.template.clone();
+    let mut manifest: Manifest = resolve_manifest(
+        &options.lang,
+        &config,
+        options.template,
+        options.reference,
+        mode,
+    )
+    .await?;
+
+    let mut given: Answers = match &options.answers {
+        Some(path) => questions::load_answers(Path::new(path))?,
+        None => Answers::new(),
+    };
+    for var in &options.vars {
+        let (key, value) = render::parse_var(var)?;
+        given.insert(key, value);
+    }
+    vars.extend(given.clone());
+    let asked: Vec<questions::Question> = manifest.questions.clone();
+    let (answers, vars): (Answers, Variables) = blocking(move || {
+        let answers: Answers =
+            questions::answer(&asked, &given, &mut vars, prompt::is_interactive())
+                .map_err(|e| e.to_string())?;
+        Ok((answers, vars))
+    })
+    .await?;
+    naming::check(&manifest.identifiers, &vars)?;
+    let excluded: Vec<String> = conditions::excluded(&manifest.when, &vars)?;
+    if let (true, Some(flag)) = (had_repo, git_flag) {
+        return Err(format!(
+            "{} is already a Git repository, `{}` only applies to new ones",
+            target.display(),
+            flag
+        )
+        .into());
+    }
+    if repo.is_none() || had_repo {
+        manifest.post.retain(|step| *step != Step::GitInit);
+    } else if git_flag.is_some() && !creates_repo(&manifest) {
+        manifest.post.push(Step::GitInit);
+    }
+    let repo: Option<&Repo> = repo
+        .as_ref()
+        .filter(|_| !had_repo && creates_repo(&manifest));
+
+    if options.dry_run {
+        return plan(&manifest, &target, &vars, &answers, &excluded, repo, mode).await;
+    }
+    doctor::preflight(&manifest, repo.is_some_and(|repo| repo.commit.is_some()))?;
+    let hooks: Vec<String> = if options.no_hooks {
+        Vec::new()
+    } else {
+        let (manifest, vars): (Manifest, Variables) = (manifest.clone(), vars.clone());
+        blocking(move || {
+            approve_hooks(&manifest, &vars, source.as_deref()).map_err(|e| e.to_string())
+        })
+        .await?
+    };
+
+    println!("Creating {} project `{}`", manifest.name, name);
+    fs::create_dir_all(parent)?;
+    let staging: TempDir = TempDir::new_in(parent, ".dev-new")?;
+    rollback.track(staging.path());
+    let project: PathBuf = staging.path().join("project");
+    let package: String = vars["crate_name"].clone();
+    let vcs: bool = repo.is_some();
+    let (staged, in_place): (Vec<Step>, Vec<Step>) = manifest
+        .post
+        .iter()
+        .partition(|step| !merging && step.is_relocatable());
+
+    generate(&manifest, &project, &vars, &excluded, mode)
+        .await
+        .map_err(|e| format!("Failed to create project `{}`: {}", name, e))?;
+    if !answers.is_empty() {
+        questions::write_answers(&project, &answers)?;
+    }
+    let (dir, step_package): (String, String) =
+        (project.to_string_lossy().to_string(), package.clone());
+    blocking(move || {
+        staged
+            .into_iter()
+            .try_for_each(|step| run_step(step, &dir, &step_package, vcs))
+    })
+    .await
+    .map_err(|e| format!("Failed to create project `{}`: {}", name, e))?;
+
+    let created: Option<TempDir> = if merging {
+        progress::step(&format!("Merging into {}", target.display()));
+        let (from, to, conflict) = (project.clone(), target.clone(), options.conflict);
+        let summary: Summary =
+            blocking(move || merge::merge_dir(&from, &to, conflict).map_err(|e| e.to_string()))
+                .await?;
+        summary.print();
+        None
+    } else {
+        fs::rename(&project, &target)?;
+        rollback.track(&absolute);
+        Some(TempDir::adopt(absolute.clone()))
+    };
+    rollback.forget(staging.path());
+    drop(staging);
+
+    let dir: String = target.to_string_lossy().to_string();
+    let (step_dir, new_repo): (String, Option<Repo>) = (dir.clone(), repo.cloned());
+    blocking(move || {
+        for step in in_place {
+            run_step(step, &step_dir, &package, vcs)?;
+        }
+        if new_repo.is_some() && !Path::new(&step_dir).join(".git").exists() {
+            return Err("The template's steps did not initialise a Git repository".to_string());
+        }
+        if let Some(repo) = &new_repo {
+            set_up_repo(repo, &step_dir)?;
+        }
+        for hook in &hooks {
+            progress::step(&format!("Running `{}`", hook));
+            bash::run_hook(hook, &step_dir)?;
+        }
+        if let Some(message) = new_repo.as_ref().and_then(|repo| repo.commit.as_ref()) {
+            progress::step("Creating initial commit");
+            bash::git_commit(&step_dir, message)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Failed to create project `{}`: {}", name, e))?;
+    if let Some(created) = created {
+        rollback.forget(created.path());
+        created.keep();
+    }
+
+    if let Some(request) = repo.and_then(|repo| repo.forge.as_ref()) {
+        publish(
+            request,
+            repo.is_some_and(|repo| repo.remote.is_none()),
+            &dir,
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "Created project `{}`, but failed to publish it: {}",
+                name, e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Create a project's remote repository and push to it.
+///
+/// # Arguments
+///
+/// * `request` - The repository to create.
+/// * `add_remote` - Whether to add the created repository as `origin`,
+///   rather than pushing to an `origin` that is already set.
+/// * `dir` - The project directory.
+async fn publish(
+    request: &forge::Request,
+    add_remote: bool,
+    dir: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    progress::step(&format!("Creating remote repository `{}`", request.name));
+    let created: forge::Created = request.send().await?;
+    if add_remote {
+        bash::git_add_remote(dir, "origin", &created.remote)?;
+    }
+    progress::step(&format!("Pushing to {}", created.remote));
+    bash::git_push(dir)?;
+    if let Some(web_url) = created.web_url {
+        println!("Published to {}", web_url);
+    }
+    Ok(())
+}
+
+/// Check whether a template's post steps initialise a Git repository.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+fn creates_repo(manifest: &Manifest) -> bool {
+    manifest
+        .post
+        .iter()
+        .any(|step| matches!(step, Step::GitInit | Step::CargoInit))
+}
+
+/// Set the initial branch and remote of a new Git repository.
+///
+/// # Arguments
+///
+/// * `repo` - The repository settings.
+/// * `dir` - The project directory.
+fn set_up_repo(repo: &Repo, dir: &str) -> Result<(), String> {
+    if let Some(branch) = &repo.branch {
+        progress::step(&format!("Setting initial branch to `{}`", branch));
+        bash::git_set_branch(dir, branch)?;
+    }
+    if let Some(remote) = &repo.remote {
+        progress::step(&format!("Adding remote origin {}", remote));
+        bash::git_add_remote(dir, "origin", remote)?;
+    }
+    Ok(())
+}
+
+/// Render a template's hooks and decide whether to run them.
+///
+/// Hooks from built-in templates and languages in the config file are
+/// trusted. Hooks from a `--template` are only run if the user confirms
+/// them, and are skipped if there is no terminal to ask on.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+/// * `vars` - The template variables.
+/// * `template` - The `--template` the manifest was loaded from, if any.
+fn approve_hooks(
+    manifest: &Manifest,
+    vars: &Variables,
+    template: Option<&str>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let hooks: Vec<String> = manifest
+        .hooks
+        .iter()
+        .map(|hook| render::render(hook, vars))
+        .collect();
+    let template: &str = match template {
+        Some(template) if !hooks.is_empty() => template,
+        _ => return Ok(hooks),
+    };
+
+    println!("The template {} wants to run:", template);
+    for hook in &hooks {
+        println!("  {}", hook);
+    }
+    if !prompt::is_interactive() {
+        println!("Skipping hooks from an untrusted template, run them yourself if needed");
+        return Ok(Vec::new());
+    }
+    if prompt::confirm("Run these commands?", false)? {
+        Ok(hooks)
+    } else {
+        println!("Skipping hooks");
+        Ok(Vec::new())
+    }
+}
+
+/// Print the files and commands creating a project would write and run.
+///
+/// Nothing is written to the project directory, but templates that are not
+/// listed file by file, such as archives and Git repositories, are fetched
+/// into the cache to find their files.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+/// * `target` - The project directory.
+/// * `vars` - The template variables.
+/// * `answers` - The answers to the template's questions.
+/// * `excluded` - Template paths whose condition is false.
+/// * `repo` - The Git repository to set up, if the project gets a new one.
+/// * `mode` - How to use cached templates.
+async fn plan(
+    manifest: &Manifest,
+    target: &Path,
+    vars: &Variables,
+    answers: &Answers,
+    excluded: &[String],
+    repo: Option<&Repo>,
+    mode: Mode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let files: Vec<&str> = manifest.files.iter().map(String::as_str).collect();
+    let sources: Vec<(PathBuf, String)> = match manifest.source() {
+        Some(Source::Remote(base_url)) => files
+            .iter()
+            .filter(|file| !conditions::is_excluded(file, excluded))
+            .map(|file| (PathBuf::from(file), format!("{}{}", base_url, file)))
+            .collect(),
+        Some(source) => {
+            let src: PathBuf = cache::fetch(&source, &files, mode).await?;
+            copy::list_files(&src, &files)?
+                .into_iter()
+                .filter(|file| !conditions::is_excluded(&file.to_string_lossy(), excluded))
+                .map(|file| {
+                    let from: String = describe(&source, &src, &file);
+                    (file, from)
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    println!(
+        "Would create {} project in {}",
+        manifest.name,
+        target.display()
+    );
+    println!("Files:");
+    for (file, from) in &sources {
+        let path: PathBuf = target.join(render::render(&file.to_string_lossy(), vars));
+        let exists: &str = if path.exists() { " (exists)" } else { "" };
+        println!("  {}{} <- {}", path.display(), exists, from);
+    }
+    if !answers.is_empty() {
+        println!("  {} <- answers", target.join(ANSWERS_FILE).display());
+    }
+    for file in &manifest.executable {
+        println!("  chmod +x {}", target.join(file).display());
+    }
+
+    println!("Commands:");
+    if manifest.post.is_empty() && manifest.hooks.is_empty() {
+        println!("  none");
+    }
+    let dir: String = target.to_string_lossy().to_string();
+    for step in &manifest.post {
+        println!(
+            "  {}",
+            step_command(*step, &dir, &vars["crate_name"], repo.is_some())
+        );
+    }
+    if let Some(branch) = repo.and_then(|repo| repo.branch.as_ref()) {
+        println!("  git -C {} symbolic-ref HEAD refs/heads/{}", dir, branch);
+    }
+    if let Some(remote) = repo.and_then(|repo| repo.remote.as_ref()) {
+        println!("  git -C {} remote add origin {}", dir, remote);
+    }
+    for hook in &manifest.hooks {
+        println!("  {} (in {})", render::render(hook, vars), dir);
+    }
+    if let Some(message) = repo.and_then(|repo| repo.commit.as_ref()) {
+        println!("  git -C {} add -A", dir);
+        println!("  git -C {} commit -m {:?}", dir, message);
+    }
+    if let Some(request) = repo.and_then(|repo| repo.forge.as_ref()) {
+        println!("  {}", request.describe());
+        if repo.is_some_and(|repo| repo.remote.is_none()) {
+            println!("  git -C {} remote add origin <created repository>", dir);
+        }
+        println!("  git -C {} push -u origin HEAD", dir);
+    }
+    Ok(())
+}
+
+/// Describe where a template file is copied from.
+///
+/// # Arguments
+///
+/// * `source` - The template's source.
+/// * `src` - The directory the template was fetched into.
+/// * `file` - The file, relative to `src`.
+fn describe(source: &Source, src: &Path, file: &Path) -> String {
+    match source {
+        Source::Archive { url, .. } => format!("{} ({})", url, file.display()),
+        Source::Git { url, reference, .. } => match reference {
+            Some(reference) => format!("{}@{} ({})", url, reference, file.display()),
+            None => format!("{} ({})", url, file.display()),
+        },
+        _ => src.join(file).display().to_string(),
+    }
+}
+
+/// Fetch and render a template into a new directory.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+/// * `dir` - The directory to generate into, which must not exist.
+/// * `vars` - The template variables.
+/// * `excluded` - Template paths whose condition is false, which are
+///   removed before rendering.
+/// * `mode` - How to use cached templates.
+async fn generate(
+    manifest: &Manifest,
+    dir: &Path,
+    vars: &Variables,
+    excluded: &[String],
+    mode: Mode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fetch(manifest, dir, excluded, mode).await?;
+    for path in excluded {
+        let path: PathBuf = dir.join(path);
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else if path.is_file() {
+            fs::remove_file(path)?;
+        }
+    }
+    copy::set_executable(dir, &manifest.executable)?;
+    progress::step("Rendering template");
+    render::render_dir(dir, vars)?;
+    Ok(())
+}
+
+/// Resolve the manifest for a language, template directory, archive or
+/// Git repository.
+///
+/// An archive or repository given as `template` is fetched into the cache
+/// and used as a template directory.
+///
+/// # Arguments
+///
+/// * `lang` - The language key.
+/// * `config` - The loaded config.
+/// * `template` - A template directory, archive URL or Git URL, which takes
+///   precedence over `lang`.
+/// * `reference` - The branch, tag or commit to use for a Git template.
+/// * `mode` - How to use cached templates.
+async fn resolve_manifest(
+    lang: &str,
+    config: &Config,
+    template: Option<String>,
+    reference: Option<String>,
+    mode: Mode,
+) -> Result<Manifest, Box<dyn std::error::Error>> {
+    if let (Some(_), Some(url)) = (&reference, &template) {
+        if !template::is_git_url(url) {
+            return Err(format!(
+                "`--ref` can only be used with a Git template, `{}` is not a Git URL",
+                url
+            )
+            .into());
+        }
+    }
+
+    let mut manifest: Manifest = match &template {
+        Some(url) if template::is_git_url(url) => {
+            let source: Source = Source::Git {
+                url: template::git_url(url).to_string(),
+                reference: reference.clone(),
+                subdir: None,
+            };
+            let dir: PathBuf = cache::fetch(&source, &[], mode).await?;
+            let name: String = template::name_from_url(url);
+            Manifest::from_directory(&dir, Some(&name)).await?
         }
-        "py" => {
-            lang::python(name).await;
-            std::process::exit(0);
+        Some(url) if archive::is_archive_url(url) => {
+            let source: Source = Source::Archive {
+                url: url.to_string(),
+                subdir: None,
+            };
+            let dir: PathBuf = cache::fetch(&source, &[], mode).await?;
+            let name: String = template::name_from_url(url);
+            Manifest::from_directory(&dir, Some(&name)).await?
         }
-        "rb" => {
-            lang::ruby(name).await;
-            std::process::exit(0);
+        Some(dir) => Manifest::from_directory(Path::new(dir), None).await?,
+        None => registry::load(lang, config, mode)
+            .await?
+            .ok_or_else(|| format!("Language `{}` not yet supported", lang))?,
+    };
+
+    if let Some(reference) = reference.filter(|_| template.is_none()) {
+        match &mut manifest.git {
+            Some(git) => git.reference = Some(reference),
+            None => return Err("`--ref` can only be used with a Git template".into()),
+        }
+    }
+    Ok(manifest)
+}
+
+/// Fetch a template's files into a new directory.
+///
+/// Only the files selected by the answers are fetched. A cached copy of a
+/// template listed file by file gains the files other answers need as they
+/// are used.
+///
+/// # Arguments
+///
+/// * `manifest` - The template manifest.
+/// * `dir` - The directory to fetch into, which must not exist.
+/// * `excluded` - Template paths whose condition is false.
+/// * `mode` - How to use cached templates.
+async fn fetch(
+    manifest: &Manifest,
+    dir: &Path,
+    excluded: &[String],
+    mode: Mode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let files: Vec<&str> = manifest.files.iter().map(String::as_str).collect();
+    let source: Source = match manifest.source() {
+        Some(source) => source,
+        None => return Ok(fs::create_dir(dir)?),
+    };
+    let selected: Vec<&str> = files
+        .into_iter()
+        .filter(|file| !conditions::is_excluded(file, excluded))
+        .collect();
+    if selected.is_empty() && !manifest.files.is_empty() {
+        return Ok(fs::create_dir(dir)?);
+    }
+    let src: PathBuf = cache::fetch(&source, &selected, mode).await?;
+    copy::copy_files(&src, selected, dir)
+}
+
+/// Get the command a post-generation step runs.
+///
+/// # Arguments
+///
+/// * `step` - The step.
+/// * `name` - The project directory.
+/// * `package` - The name of the project's package.
+/// * `vcs` - Whether Cargo initialises a Git repository.
+fn step_command(step: Step, name: &str, package: &str, vcs: bool) -> String {
+    match step {
+        Step::Virtualenv => format!("python3 -m venv {}/venv", name),
+        Step::GitInit => format!("git init --quiet {}", name),
+        Step::CargoInit if vcs => format!("cargo init --name {} --vcs git {}", package, name),
+        Step::CargoInit => format!("cargo init --name {} --vcs none {}", package, name),
+    }
+}
+
+/// Run a post-generation step.
+///
+/// # Arguments
+///
+/// * `step` - The step to run.
+/// * `dir` - The project directory.
+/// * `package` - The name of the project's package.
+/// * `vcs` - Whether Cargo initialises a Git repository.
+fn run_step(step: Step, dir: &String, package: &str, vcs: bool) -> Result<(), String> {
+    match step {
+        Step::Virtualenv => {
+            progress::step("Creating virtualenv");
+            bash::make_virtualenv(dir)
+        }
+        Step::GitInit => {
+            progress::step("Initialising Git repository");
+            bash::git_init(dir)
         }
-        "rs" => {
-            lang::rust(name);
-            std::process::exit(0);
+        Step::CargoInit => {
+            progress::step("Initialising Cargo package");
+            bash::cargo_init(dir, package, vcs)
         }
-        _ => {
-            eprintln!("Language `{}` not yet supported", lang);
-            std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::template::MANIFEST_FILE;
+
+    /// Build options that create `name` in `path` from a template directory.
+    fn options(name: &str, template: &Path, path: &Path) -> Options {
+        Options {
+            name: name.to_string(),
+            lang: "py".to_string(),
+            template: Some(template.to_string_lossy().to_string()),
+            reference: None,
+            vars: Vec::new(),
+            offline: false,
+            refresh: false,
+            path: Some(path.to_string_lossy().to_string()),
+            into: None,
+            force: false,
+            conflict: Conflict::Skip,
+            dry_run: false,
+            no_hooks: true,
+            answers: None,
+            no_git: true,
+            branch: None,
+            commit: None,
+            message: None,
+            remote: None,
+            org: None,
+            create_remote: false,
+            private: None,
+            description: None,
         }
     }
+
+    #[tokio::test]
+    async fn test_run_interrupted() {
+        let dir: &Path = Path::new("_test_execute_interrupted");
+        let template: PathBuf = dir.join("template");
+        fs::create_dir_all(&template).unwrap();
+
+        // A server that accepts the template download but never answers.
+        let listener: std::net::TcpListener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let manifest: String = format!(
+            "name: Test\nbase_url: http://{}/\nfiles: [README.md]\n",
+            listener.local_addr().unwrap()
+        );
+        fs::write(template.join(MANIFEST_FILE), manifest).unwrap();
+        let (accepted, downloading) = tokio::sync::oneshot::channel::<()>();
+        std::thread::spawn(move || {
+            let _stream = listener.accept().unwrap();
+            let _ = accepted.send(());
+            std::thread::sleep(std::time::Duration::from_secs(60));
+        });
+
+        let interrupt = tokio::time::timeout(std::time::Duration::from_secs(10), downloading);
+        let error: String = run(options("app", &template, &dir.join("app")), interrupt)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Interrupted, the project was not created");
+
+        let leftover: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name != "template")
+            .collect();
+        assert!(leftover.is_empty(), "left behind {:?}", leftover);
+        fs::remove_dir_all(dir).unwrap();
+        let _ = fs::remove_dir(cache::dir());
+        let _ = fs::remove_dir(cache::dir().parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_into_root() {
+        let options: Options = Options {
+            into: Some("/".to_string()),
+            ..options("app", Path::new("_test_execute_root"), Path::new("/"))
+        };
+        let error: String = run(options, std::future::pending::<()>())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Cannot generate into the filesystem root");
+    }
+
+    #[tokio::test]
+    async fn test_run_in_parent_repo() {
+        let dir: &Path = Path::new("_test_execute_parent_repo");
+        let template: PathBuf = dir.join("template");
+        fs::create_dir_all(&template).unwrap();
+        fs::write(
+            template.join(MANIFEST_FILE),
+            "name: Test\npost: [cargo-init]\n",
+        )
+        .unwrap();
+        bash::git_init(&dir.to_string_lossy().to_string()).unwrap();
+
+        let project: PathBuf = dir.join("app");
+        let options: Options = Options {
+            no_git: false,
+            branch: Some("trunk".to_string()),
+            commit: Some(false),
+            remote: Some("git@example.com:org/{name}.git".to_string()),
+            ..options("app", &template, &project)
+        };
+        run(options, std::future::pending::<()>()).await.unwrap();
+
+        let git = |args: &[&str]| -> String {
+            let output: std::process::Output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&project)
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        assert!(project.join(".git").is_dir());
+        assert_eq!(git(&["symbolic-ref", "HEAD"]), "refs/heads/trunk");
+        assert_eq!(
+            git(&["remote", "get-url", "origin"]),
+            "git@example.com:org/app.git"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_git_options() {
+        let dir: &Path = Path::new("_test_execute_git_options");
+        let template: PathBuf = dir.join("template");
+        fs::create_dir_all(&template).unwrap();
+        fs::write(template.join(MANIFEST_FILE), "name: Test\n").unwrap();
+        fs::write(template.join("README.md"), "readme").unwrap();
+
+        let project: PathBuf = dir.join("app");
+        let options = |into: Option<String>| Options {
+            no_git: false,
+            branch: Some("trunk".to_string()),
+            commit: Some(false),
+            into,
+            ..options("app", &template, &project)
+        };
+        run(options(None), std::future::pending::<()>())
+            .await
+            .unwrap();
+        let head: String = fs::read_to_string(project.join(".git/HEAD")).unwrap();
+        assert_eq!(head.trim(), "ref: refs/heads/trunk");
+
+        let into: Option<String> = Some(project.to_string_lossy().to_string());
+        let error: String = run(options(into), std::future::pending::<()>())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with("is already a Git repository, `--branch` only applies to new ones"));
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
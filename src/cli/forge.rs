@@ -0,0 +1,343 @@
+//! Create repositories on GitHub, Gitea and GitLab compatible forges.
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use serde_json::{json, Value};
+
+use crate::cli::config::{Forge, ForgeKind, Protocol};
+use crate::cli::download;
+
+/// The environment variable that overrides the forge token in the config.
+const TOKEN_VAR: &str = "DEV_CLI_FORGE_TOKEN";
+
+/// A repository to create on a forge.
+///
+/// # Fields
+///
+/// * `kind` - The API the forge speaks.
+/// * `api_url` - The base URL of the API, without a trailing slash.
+/// * `token` - The API token.
+/// * `protocol` - Whether the returned remote uses SSH or HTTPS.
+/// * `name` - The name of the repository.
+/// * `org` - The organisation or group to create it in, instead of the
+///   token's user.
+/// * `description` - A short description of the repository.
+/// * `private` - Whether the repository is private.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub kind: ForgeKind,
+    pub api_url: String,
+    pub token: String,
+    pub protocol: Protocol,
+    pub name: String,
+    pub org: Option<String>,
+    pub description: Option<String>,
+    pub private: bool,
+}
+
+/// A repository created on a forge.
+///
+/// # Fields
+///
+/// * `remote` - The URL to push to.
+/// * `web_url` - The repository's page, if the forge returned one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Created {
+    pub remote: String,
+    pub web_url: Option<String>,
+}
+
+/// Implement the `Request` struct.
+impl Request {
+    /// Build a request from the forge settings.
+    ///
+    /// Fails if there is no token or API URL, so nothing is created before
+    /// the problem is reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The forge settings from the config.
+    /// * `name` - The name of the repository.
+    /// * `org` - The organisation, overriding the config.
+    /// * `description` - A short description of the repository.
+    /// * `private` - Whether the repository is private, overriding the config.
+    pub fn new(
+        settings: &Forge,
+        name: &str,
+        org: Option<String>,
+        description: Option<String>,
+        private: Option<bool>,
+    ) -> Result<Self, String> {
+        let api_url: String = match (&settings.api_url, settings.kind) {
+            (Some(url), _) => url.trim_end_matches('/').to_string(),
+            (None, ForgeKind::Github) => "https://api.github.com".to_string(),
+            (None, ForgeKind::Gitlab) => "https://gitlab.com/api/v4".to_string(),
+            (None, ForgeKind::Gitea) => {
+                return Err("Set `forge.api_url` in the config to create Gitea repositories".into())
+            }
+        };
+        let token: String = std::env::var(TOKEN_VAR)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .or_else(|| settings.token.clone())
+            .ok_or_else(|| {
+                format!(
+                    "No forge token, set {} or `forge.token` in the config",
+                    TOKEN_VAR
+                )
+            })?;
+
+        Ok(Request {
+            kind: settings.kind,
+            api_url,
+            token,
+            protocol: settings.protocol,
+            name: name.to_string(),
+            org: org.or_else(|| settings.org.clone()),
+            description,
+            private: private.unwrap_or(settings.private),
+        })
+    }
+
+    /// Get the endpoint that creates the repository.
+    fn endpoint(&self) -> String {
+        match (self.kind, &self.org) {
+            (ForgeKind::Gitlab, _) => format!("{}/projects", self.api_url),
+            (_, Some(org)) => format!("{}/orgs/{}/repos", self.api_url, org),
+            (_, None) => format!("{}/user/repos", self.api_url),
+        }
+    }
+
+    /// Describe the request, e.g. for `--dry-run`.
+    pub fn describe(&self) -> String {
+        let visibility: &str = if self.private { "private" } else { "public" };
+        let path: String = match &self.org {
+            Some(org) => format!("{}/{}", org, self.name),
+            None => self.name.clone(),
+        };
+        format!(
+            "POST {} (create {} repository {})",
+            self.endpoint(),
+            visibility,
+            path
+        )
+    }
+
+    /// Get the JSON body of the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace_id` - The id of the GitLab group to create the project in.
+    fn body(&self, namespace_id: Option<u64>) -> Value {
+        match self.kind {
+            ForgeKind::Gitlab => {
+                let visibility: &str = if self.private { "private" } else { "public" };
+                let mut body: Value = json!({
+                    "name": self.name,
+                    "path": self.name,
+                    "description": self.description,
+                    "visibility": visibility,
+                });
+                if let Some(id) = namespace_id {
+                    body["namespace_id"] = json!(id);
+                }
+                body
+            }
+            _ => json!({
+                "name": self.name,
+                "description": self.description,
+                "private": self.private,
+            }),
+        }
+    }
+
+    /// Add the headers the forge needs to a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to authenticate.
+    fn authorise(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request: reqwest::RequestBuilder = request.header(USER_AGENT, "dev-cli");
+        match self.kind {
+            ForgeKind::Github => request
+                .bearer_auth(&self.token)
+                .header(ACCEPT, "application/vnd.github+json"),
+            ForgeKind::Gitea => request.header(AUTHORIZATION, format!("token {}", self.token)),
+            ForgeKind::Gitlab => request.header("PRIVATE-TOKEN", &self.token),
+        }
+    }
+
+    /// Create the repository.
+    pub async fn send(&self) -> Result<Created, Box<dyn std::error::Error>> {
+        let client: reqwest::Client = download::client()?;
+        let namespace_id: Option<u64> = match (self.kind, &self.org) {
+            (ForgeKind::Gitlab, Some(org)) => Some(self.namespace_id(&client, org).await?),
+            _ => None,
+        };
+
+        let resp: reqwest::Response = self
+            .authorise(client.post(self.endpoint()))
+            .json(&self.body(namespace_id))
+            .send()
+            .await?;
+        let repo: Value = parse(resp).await?;
+        Ok(created(self.kind, self.protocol, &repo)?)
+    }
+
+    /// Look up the id of a GitLab group.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to send the request with.
+    /// * `org` - The full path of the group, e.g. `platform/services`.
+    async fn namespace_id(
+        &self,
+        client: &reqwest::Client,
+        org: &str,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let url: String = format!("{}/namespaces/{}", self.api_url, org.replace('/', "%2F"));
+        let resp: reqwest::Response = self.authorise(client.get(url)).send().await?;
+        let namespace: Value = parse(resp)
+            .await
+            .map_err(|e| format!("Failed to find GitLab group `{}`: {}", org, e))?;
+        namespace["id"]
+            .as_u64()
+            .ok_or_else(|| format!("GitLab group `{}` has no id", org).into())
+    }
+}
+
+/// Read a JSON response, turning an error status into the forge's message.
+///
+/// # Arguments
+///
+/// * `resp` - The response.
+async fn parse(resp: reqwest::Response) -> Result<Value, Box<dyn std::error::Error>> {
+    let status: reqwest::StatusCode = resp.status();
+    let text: String = resp.text().await?;
+    if status.is_success() {
+        return Ok(serde_json::from_str(&text)?);
+    }
+    Err(format!("{}: {}", status, error_message(&text)).into())
+}
+
+/// Get the message from a forge's error response.
+///
+/// # Arguments
+///
+/// * `text` - The body of the response.
+fn error_message(text: &str) -> String {
+    let body: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+    let mut message: String = match &body["message"] {
+        Value::String(message) => message.clone(),
+        Value::Null => text.trim().to_string(),
+        message => message.to_string(),
+    };
+    for error in body["errors"].as_array().into_iter().flatten() {
+        if let Some(detail) = error["message"].as_str() {
+            message.push_str(&format!(", {}", detail));
+        }
+    }
+    message
+}
+
+/// Get the remote of a created repository from the forge's response.
+///
+/// # Arguments
+///
+/// * `kind` - The API the forge speaks.
+/// * `protocol` - Whether to use the SSH or HTTPS URL.
+/// * `repo` - The created repository.
+fn created(kind: ForgeKind, protocol: Protocol, repo: &Value) -> Result<Created, String> {
+    let (ssh, https, web): (&str, &str, &str) = match kind {
+        ForgeKind::Gitlab => ("ssh_url_to_repo", "http_url_to_repo", "web_url"),
+        _ => ("ssh_url", "clone_url", "html_url"),
+    };
+    let key: &str = match protocol {
+        Protocol::Ssh => ssh,
+        Protocol::Https => https,
+    };
+    let remote: &str = repo[key]
+        .as_str()
+        .ok_or_else(|| format!("The forge did not return `{}` for the repository", key))?;
+    Ok(Created {
+        remote: remote.to_string(),
+        web_url: repo[web].as_str().map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build forge settings with a token.
+    fn settings(kind: ForgeKind) -> Forge {
+        Forge {
+            kind,
+            api_url: Some("http://localhost:3000/api/v1/".to_string()),
+            token: Some("secret".to_string()),
+            ..Forge::default()
+        }
+    }
+
+    #[test]
+    fn test_request_new() {
+        let request: Request =
+            Request::new(&settings(ForgeKind::Gitea), "app", None, None, Some(false)).unwrap();
+        assert_eq!(
+            request.endpoint(),
+            "http://localhost:3000/api/v1/user/repos"
+        );
+        assert!(!request.private);
+
+        let gitea: Forge = Forge {
+            api_url: None,
+            ..settings(ForgeKind::Gitea)
+        };
+        assert!(Request::new(&gitea, "app", None, None, None).is_err());
+
+        let github: Forge = Forge {
+            api_url: None,
+            ..settings(ForgeKind::Github)
+        };
+        let request: Request =
+            Request::new(&github, "app", Some("acme".to_string()), None, None).unwrap();
+        assert_eq!(
+            request.describe(),
+            "POST https://api.github.com/orgs/acme/repos (create private repository acme/app)"
+        );
+    }
+
+    #[test]
+    fn test_body() {
+        let request: Request =
+            Request::new(&settings(ForgeKind::Gitlab), "app", None, None, None).unwrap();
+        let body: Value = request.body(Some(7));
+        assert_eq!(body["visibility"], "private");
+        assert_eq!(body["namespace_id"], 7);
+        assert_eq!(request.endpoint(), "http://localhost:3000/api/v1/projects");
+    }
+
+    #[test]
+    fn test_created() {
+        let repo: Value = json!({
+            "ssh_url": "git@github.com:acme/app.git",
+            "clone_url": "https://github.com/acme/app.git",
+            "html_url": "https://github.com/acme/app",
+        });
+        let created: Created = created(ForgeKind::Github, Protocol::Https, &repo).unwrap();
+        assert_eq!(created.remote, "https://github.com/acme/app.git");
+        assert_eq!(
+            created.web_url.as_deref(),
+            Some("https://github.com/acme/app")
+        );
+        assert!(super::created(ForgeKind::Gitlab, Protocol::Ssh, &repo).is_err());
+    }
+
+    #[test]
+    fn test_error_message() {
+        let text: &str = r#"{"message": "Repository creation failed.", "errors": [{"message": "name already exists on this account"}]}"#;
+        assert_eq!(
+            error_message(text),
+            "Repository creation failed., name already exists on this account"
+        );
+        assert_eq!(error_message("Bad Gateway\n"), "Bad Gateway");
+    }
+}
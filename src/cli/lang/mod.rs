@@ -1,9 +0,0 @@
-mod cpp;
-mod python;
-mod ruby;
-mod rust;
-
-pub use cpp::cpp;
-pub use python::python;
-pub use ruby::ruby;
-pub use rust::rust;
@@ -0,0 +1,153 @@
+//! List the languages `project new` can generate.
+use serde::Serialize;
+
+use crate::cli::cache;
+use crate::cli::config::Config;
+use crate::cli::template::{registry, Manifest, Source};
+
+/// A language that can be passed to `--lang`.
+///
+/// # Fields
+///
+/// * `key` - The value passed to `--lang`.
+/// * `name` - The display name of the template.
+/// * `description` - A short description of the template.
+/// * `origin` - `built-in` or `config`.
+/// * `source` - Where the template's files are fetched from.
+/// * `reference` - The Git ref of a Git template.
+/// * `toolchain` - The programs the template needs.
+/// * `cached` - Whether the template's files are cached, `None` for
+///   templates that are not cached, such as local directories.
+/// * `error` - Why the template's manifest could not be loaded.
+#[derive(Serialize, Debug)]
+pub struct Lang {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub origin: &'static str,
+    pub source: Option<String>,
+    pub reference: Option<String>,
+    pub toolchain: Vec<String>,
+    pub cached: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// Implement the `Lang` struct.
+impl Lang {
+    /// Describe a language from its manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The value passed to `--lang`.
+    /// * `origin` - `built-in` or `config`.
+    /// * `manifest` - The template manifest.
+    fn new(key: String, origin: &'static str, manifest: &Manifest) -> Self {
+        let source: Option<Source> = manifest.source();
+        let (location, reference) = match &source {
+            Some(Source::Remote(url)) | Some(Source::Archive { url, .. }) => {
+                (Some(url.clone()), None)
+            }
+            Some(Source::Git { url, reference, .. }) => (Some(url.clone()), reference.clone()),
+            Some(Source::Local(path)) => (Some(path.display().to_string()), None),
+            None => (None, None),
+        };
+        Lang {
+            key,
+            name: manifest.name.clone(),
+            description: manifest.description.clone(),
+            origin,
+            source: location,
+            reference,
+            toolchain: manifest.toolchain(),
+            cached: source.as_ref().and_then(cache::is_cached),
+            error: None,
+        }
+    }
+
+    /// Print a language to the console.
+    pub fn print(&self) {
+        println!(
+            "\x1b[1m{}\x1b[0m - {} ({})",
+            self.key, self.name, self.origin
+        );
+        if let Some(error) = &self.error {
+            println!("    {}\n", error);
+            return;
+        }
+        if !self.description.is_empty() {
+            println!("    {}", self.description);
+        }
+        if let Some(source) = &self.source {
+            match &self.reference {
+                Some(reference) => println!("    {} @ {}", source, reference),
+                None => println!("    {}", source),
+            }
+        }
+        if !self.toolchain.is_empty() {
+            println!("    requires: {}", self.toolchain.join(", "));
+        }
+        match self.cached {
+            Some(true) => println!("    cached\n"),
+            Some(false) => println!("    not cached\n"),
+            None => println!(),
+        }
+    }
+}
+
+/// List every registered language.
+///
+/// Manifests are loaded from the cache or the embedded copies, so listing
+/// never touches the network.
+///
+/// # Arguments
+///
+/// * `config` - The loaded config.
+pub async fn list(config: &Config) -> Vec<Lang> {
+    let mut langs: Vec<Lang> = Vec::new();
+    for key in registry::keys(config) {
+        let origin: &str = match config.languages.contains_key(&key) {
+            true => "config",
+            false => "built-in",
+        };
+        let lang: Lang = match registry::load(&key, config, cache::Mode::Offline).await {
+            Ok(Some(manifest)) => Lang::new(key, origin, &manifest),
+            Ok(None) => continue,
+            Err(e) => Lang {
+                key,
+                name: String::new(),
+                description: String::new(),
+                origin,
+                source: None,
+                reference: None,
+                toolchain: Vec::new(),
+                cached: None,
+                error: Some(e.to_string()),
+            },
+        };
+        langs.push(lang);
+    }
+    langs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list() {
+        let config: Config = Config::from_yaml(
+            "languages:\n  svc:\n    name: Service\n    base_url: ./svc\n    post: [git-init]\n",
+        )
+        .unwrap();
+        let langs: Vec<Lang> = list(&config).await;
+
+        let svc: &Lang = langs.iter().find(|lang| lang.key == "svc").unwrap();
+        assert_eq!(svc.origin, "config");
+        assert_eq!(svc.toolchain, vec!["git"]);
+        assert_eq!(svc.cached, None);
+
+        let py: &Lang = langs.iter().find(|lang| lang.key == "py").unwrap();
+        assert_eq!(py.origin, "built-in");
+        assert!(py.toolchain.contains(&"poetry".to_string()));
+    }
+}
@@ -0,0 +1,279 @@
+//! Merge a generated project into an existing directory.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::prompt;
+
+/// What to do when a generated file already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// Leave the existing file alone.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+    /// Rename the existing file to `<file>.orig`, or `<file>.orig.N` if that
+    /// is taken, and write the new one.
+    Keep,
+    /// Ask for each file, skipping if there is no terminal to ask on.
+    Prompt,
+}
+
+/// Implement the `Conflict` enum.
+impl Conflict {
+    /// The values accepted by `--conflict`.
+    pub const VALUES: [&'static str; 4] = ["skip", "overwrite", "keep", "prompt"];
+
+    /// Parse a policy from a `--conflict` value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - One of `Conflict::VALUES`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "skip" => Some(Conflict::Skip),
+            "overwrite" => Some(Conflict::Overwrite),
+            "keep" => Some(Conflict::Keep),
+            "prompt" => Some(Conflict::Prompt),
+            _ => None,
+        }
+    }
+}
+
+/// The files written by a merge, relative to the target directory.
+///
+/// # Fields
+///
+/// * `created` - Files that did not exist.
+/// * `overwritten` - Files that replaced an existing file.
+/// * `kept` - Files written after moving the existing file to `.orig`.
+/// * `skipped` - Files left as they were.
+/// * `unchanged` - Files identical to the existing file.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub created: Vec<PathBuf>,
+    pub overwritten: Vec<PathBuf>,
+    pub kept: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    pub unchanged: Vec<PathBuf>,
+}
+
+/// Implement the `Summary` struct.
+impl Summary {
+    /// Print what was written.
+    pub fn print(&self) {
+        for (label, files) in [
+            ("Created", &self.created),
+            ("Overwrote", &self.overwritten),
+            ("Kept existing as .orig", &self.kept),
+            ("Skipped", &self.skipped),
+        ] {
+            for file in files {
+                println!("  {}: {}", label, file.display());
+            }
+        }
+        println!(
+            "{} created, {} overwritten, {} kept as .orig, {} skipped, {} unchanged",
+            self.created.len(),
+            self.overwritten.len(),
+            self.kept.len(),
+            self.skipped.len(),
+            self.unchanged.len()
+        );
+    }
+}
+
+/// Move every file from a generated project into an existing directory.
+///
+/// # Arguments
+///
+/// * `from` - The generated project.
+/// * `to` - The directory to merge into.
+/// * `policy` - What to do when a file already exists.
+pub fn merge_dir(from: &Path, to: &Path, policy: Conflict) -> std::io::Result<Summary> {
+    let mut summary: Summary = Summary::default();
+    let mut policy: Conflict = policy;
+    merge(from, to, Path::new(""), &mut policy, &mut summary)?;
+    Ok(summary)
+}
+
+/// Recursively merge a directory.
+///
+/// # Arguments
+///
+/// * `from` - The directory to merge.
+/// * `to` - The directory to merge into.
+/// * `rel` - The path of `from` relative to the project root.
+/// * `policy` - What to do when a file already exists, which a prompt may
+///   change for every remaining file.
+/// * `summary` - The files written so far.
+fn merge(
+    from: &Path,
+    to: &Path,
+    rel: &Path,
+    policy: &mut Conflict,
+    summary: &mut Summary,
+) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry: fs::DirEntry = entry?;
+        let rel: PathBuf = rel.join(entry.file_name());
+        let source: PathBuf = entry.path();
+        let target: PathBuf = to.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() && !target.is_file() {
+            merge(&source, &target, &rel, policy, summary)?;
+            continue;
+        }
+        if !target.exists() {
+            fs::rename(&source, &target)?;
+            summary.created.push(rel);
+            continue;
+        }
+        if target.is_dir() || source.is_dir() {
+            summary.skipped.push(rel);
+            continue;
+        }
+        if fs::read(&source)? == fs::read(&target)? {
+            summary.unchanged.push(rel);
+            continue;
+        }
+
+        match resolve(policy, &rel)? {
+            Conflict::Overwrite => {
+                fs::rename(&source, &target)?;
+                summary.overwritten.push(rel);
+            }
+            Conflict::Keep => {
+                fs::rename(&target, orig_path(&target))?;
+                fs::rename(&source, &target)?;
+                summary.kept.push(rel);
+            }
+            _ => summary.skipped.push(rel),
+        }
+    }
+    Ok(())
+}
+
+/// Decide what to do with a conflicting file, prompting if needed.
+///
+/// # Arguments
+///
+/// * `policy` - The current policy, updated if the user answers for all files.
+/// * `rel` - The conflicting file, relative to the project root.
+fn resolve(policy: &mut Conflict, rel: &Path) -> std::io::Result<Conflict> {
+    if *policy != Conflict::Prompt {
+        return Ok(*policy);
+    }
+    if !prompt::is_interactive() {
+        return Ok(Conflict::Skip);
+    }
+
+    loop {
+        let answer: String = prompt::ask(&format!(
+            "{} already exists. [s]kip, [o]verwrite, [k]eep both, or S/O/K for all: ",
+            rel.display()
+        ))?;
+
+        let choice: Conflict = match answer.to_lowercase().as_str() {
+            "s" => Conflict::Skip,
+            "o" => Conflict::Overwrite,
+            "k" => Conflict::Keep,
+            _ => continue,
+        };
+        if answer.chars().all(char::is_uppercase) {
+            *policy = choice;
+        }
+        return Ok(choice);
+    }
+}
+
+/// Get the path an existing file is moved to when keeping both.
+///
+/// Numbers the suffix rather than replace an earlier `.orig` file.
+///
+/// # Arguments
+///
+/// * `path` - The existing file.
+fn orig_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".orig");
+    let mut orig: PathBuf = path.with_file_name(&name);
+    let mut n: u32 = 1;
+    while fs::symlink_metadata(&orig).is_ok() {
+        orig = path.with_file_name(format!("{}.{}", name.to_string_lossy(), n));
+        n += 1;
+    }
+    orig
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a generated project and an existing directory to merge into.
+    fn setup(dir: &str) -> (PathBuf, PathBuf) {
+        let from: PathBuf = PathBuf::from(format!("{}/from", dir));
+        let to: PathBuf = PathBuf::from(format!("{}/to", dir));
+        fs::create_dir_all(from.join("src")).unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(from.join("README.md"), "new readme").unwrap();
+        fs::write(from.join("LICENSE"), "license").unwrap();
+        fs::write(from.join("src/main.py"), "main").unwrap();
+        fs::write(to.join("README.md"), "old readme").unwrap();
+        fs::write(to.join("LICENSE"), "license").unwrap();
+        (from, to)
+    }
+
+    #[test]
+    fn test_merge_dir_skip() {
+        let (from, to) = setup("_test_merge_skip");
+        let summary: Summary = merge_dir(&from, &to, Conflict::Skip).unwrap();
+
+        assert_eq!(summary.created, vec![PathBuf::from("src/main.py")]);
+        assert_eq!(summary.skipped, vec![PathBuf::from("README.md")]);
+        assert_eq!(summary.unchanged, vec![PathBuf::from("LICENSE")]);
+        assert_eq!(
+            fs::read_to_string(to.join("README.md")).unwrap(),
+            "old readme"
+        );
+        fs::remove_dir_all("_test_merge_skip").unwrap();
+    }
+
+    #[test]
+    fn test_merge_dir_keep() {
+        let (from, to) = setup("_test_merge_keep");
+        let summary: Summary = merge_dir(&from, &to, Conflict::Keep).unwrap();
+
+        assert_eq!(summary.kept, vec![PathBuf::from("README.md")]);
+        assert_eq!(
+            fs::read_to_string(to.join("README.md")).unwrap(),
+            "new readme"
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("README.md.orig")).unwrap(),
+            "old readme"
+        );
+
+        let (from, to) = setup("_test_merge_keep");
+        fs::write(to.join("README.md.orig"), "older readme").unwrap();
+        merge_dir(&from, &to, Conflict::Keep).unwrap();
+        assert_eq!(
+            fs::read_to_string(to.join("README.md.orig")).unwrap(),
+            "older readme"
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("README.md.orig.1")).unwrap(),
+            "old readme"
+        );
+        fs::remove_dir_all("_test_merge_keep").unwrap();
+    }
+
+    #[test]
+    fn test_conflict_parse() {
+        for value in Conflict::VALUES {
+            assert!(Conflict::parse(value).is_some());
+        }
+        assert_eq!(Conflict::parse("merge"), None);
+    }
+}
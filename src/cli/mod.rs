@@ -1,15 +1,28 @@
+mod archive;
 mod bash;
+mod cache;
 pub mod commands;
+mod config;
+mod copy;
+mod doctor;
 mod download;
 mod execute;
-mod lang;
+mod forge;
+mod langs;
+mod merge;
 mod process_matches;
+mod progress;
+mod prompt;
 mod search;
+mod template;
 mod utils;
 
-pub use execute::execute;
+pub use execute::{execute, Options};
+pub use merge::Conflict;
 pub use process_matches::process_matches;
 pub use search::query;
+pub use utils::get_flag;
+pub use utils::get_many_values;
 pub use utils::get_optional_value;
 pub use utils::get_required_value;
 pub use utils::get_value_or_default;
@@ -16,6 +16,18 @@ pub async fn process_matches(matches: ArgMatches) {
             process_project_subcommand(matches, cmd).await;
         }
 
+        Some("cache") => {
+            let matches: &ArgMatches = matches.subcommand_matches("cache").unwrap();
+            process_cache_subcommand(matches);
+        }
+
+        Some("doctor") => {
+            let config: cli::config::Config = cli::config::Config::load_or_exit().await;
+            if !cli::doctor::run(&config).await {
+                std::process::exit(1);
+            }
+        }
+
         Some("search") => {
             let query: String = cli::get_required_value(&matches, "search", "query");
             let page_num: usize = cli::get_value_or_default(&matches, "search", "page", 1);
@@ -33,9 +45,111 @@ pub async fn process_matches(matches: ArgMatches) {
 /// * `matches` - The matches from the `project` subcommand.
 /// * `cmd` - The subcommand of the `project` subcommand.
 async fn process_project_subcommand(matches: &ArgMatches, cmd: Option<&str>) {
+    if let Some("list-langs") = cmd {
+        let json: bool = cli::get_flag(matches, "list-langs", "json");
+        let config: cli::config::Config = cli::config::Config::load_or_exit().await;
+        let langs: Vec<cli::langs::Lang> = cli::langs::list(&config).await;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&langs).unwrap());
+        } else {
+            for lang in langs {
+                lang.print();
+            }
+        }
+    }
+
     if let Some("new") = cmd {
         let name: String = cli::get_required_value(matches, "new", "name");
         let lang: String = cli::get_value_or_default(matches, "new", "lang", "py".to_string());
-        cli::execute(name, lang).await
+        let template: Option<String> = cli::get_optional_value(matches, "new", "template");
+        let reference: Option<String> = cli::get_optional_value(matches, "new", "ref");
+        let vars: Vec<String> = cli::get_many_values(matches, "new", "var");
+        let offline: bool = cli::get_flag(matches, "new", "offline");
+        let refresh: bool = cli::get_flag(matches, "new", "refresh");
+        let path: Option<String> = cli::get_optional_value(matches, "new", "path");
+        let into: Option<String> = cli::get_optional_value(matches, "new", "into");
+        let force: bool = cli::get_flag(matches, "new", "force");
+        let conflict: String = cli::get_required_value(matches, "new", "conflict");
+        let dry_run: bool = cli::get_flag(matches, "new", "dry-run");
+        let no_hooks: bool = cli::get_flag(matches, "new", "no-hooks");
+        let answers: Option<String> = cli::get_optional_value(matches, "new", "answers");
+        let no_git: bool = cli::get_flag(matches, "new", "no-git");
+        let branch: Option<String> = cli::get_optional_value(matches, "new", "branch");
+        let commit: Option<bool> = match (
+            cli::get_flag(matches, "new", "commit"),
+            cli::get_flag(matches, "new", "no-commit"),
+        ) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        let message: Option<String> = cli::get_optional_value(matches, "new", "message");
+        let remote: Option<String> = cli::get_optional_value(matches, "new", "remote");
+        let org: Option<String> = cli::get_optional_value(matches, "new", "org");
+        let create_remote: bool = cli::get_flag(matches, "new", "create-remote");
+        let private: Option<bool> = match (
+            cli::get_flag(matches, "new", "private"),
+            cli::get_flag(matches, "new", "public"),
+        ) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        let description: Option<String> = cli::get_optional_value(matches, "new", "description");
+        cli::execute(cli::Options {
+            name,
+            lang,
+            template,
+            reference,
+            vars,
+            offline,
+            refresh,
+            path,
+            into,
+            force,
+            conflict: cli::Conflict::parse(&conflict).unwrap(),
+            dry_run,
+            no_hooks,
+            answers,
+            no_git,
+            branch,
+            commit,
+            message,
+            remote,
+            org,
+            create_remote,
+            private,
+            description,
+        })
+        .await
+    }
+}
+
+/// Process the matches from the `cache` subcommand.
+///
+/// # Arguments
+///
+/// * `matches` - The matches from the `cache` subcommand.
+fn process_cache_subcommand(matches: &ArgMatches) {
+    let result: Result<(), Box<dyn std::error::Error>> = match matches.subcommand_name() {
+        Some("list") => cli::cache::list().map(|entries| {
+            if entries.is_empty() {
+                println!("No cached templates in {}", cli::cache::dir().display());
+            }
+            for entry in entries {
+                entry.print();
+            }
+        }),
+        Some("clear") => {
+            let key: Option<String> = cli::get_optional_value(matches, "clear", "key");
+            cli::cache::clear(key.as_deref())
+                .map(|count| println!("Removed {} cached template(s)", count))
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
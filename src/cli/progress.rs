@@ -0,0 +1,112 @@
+//! Progress output while a project is generated.
+//!
+//! On a terminal the file count is redrawn in place, otherwise every update
+//! is printed on its own line so logs show where generation stopped.
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+
+use crate::cli::cache::format_size;
+
+/// Files downloaded so far.
+///
+/// # Fields
+///
+/// * `done` - The number of files downloaded.
+/// * `total` - The number of files to download.
+/// * `bytes` - The bytes downloaded.
+/// * `drawing` - Whether a count is drawn on the current terminal line.
+struct Files {
+    done: usize,
+    total: usize,
+    bytes: u64,
+    drawing: bool,
+}
+
+static FILES: Mutex<Files> = Mutex::new(Files {
+    done: 0,
+    total: 0,
+    bytes: 0,
+    drawing: false,
+});
+
+/// Announce the step that is starting, e.g. `Creating virtualenv`.
+///
+/// # Arguments
+///
+/// * `message` - The step.
+pub fn step(message: &str) {
+    end_line(&mut FILES.lock().unwrap());
+    println!("{}...", message);
+}
+
+/// Start counting downloaded files.
+///
+/// # Arguments
+///
+/// * `total` - The number of files to download.
+pub fn start_files(total: usize) {
+    let mut files = FILES.lock().unwrap();
+    end_line(&mut files);
+    files.done = 0;
+    files.total = total;
+    files.bytes = 0;
+}
+
+/// Record a downloaded file.
+///
+/// # Arguments
+///
+/// * `file` - The path of the file.
+/// * `bytes` - The size of the file.
+pub fn file_done(file: &str, bytes: u64) {
+    let mut files = FILES.lock().unwrap();
+    files.done += 1;
+    files.bytes += bytes;
+    let count: String = format_files(files.done, files.total, files.bytes);
+
+    if std::io::stdout().is_terminal() {
+        print!("\r\x1b[2K{}", count);
+        let _ = std::io::stdout().flush();
+        files.drawing = true;
+    } else {
+        println!("{} {}", count, file);
+    }
+}
+
+/// Stop counting downloaded files.
+pub fn finish_files() {
+    end_line(&mut FILES.lock().unwrap());
+}
+
+/// Move past a count drawn on the current line.
+///
+/// # Arguments
+///
+/// * `files` - The file count.
+fn end_line(files: &mut Files) {
+    if files.drawing {
+        println!();
+        files.drawing = false;
+    }
+}
+
+/// Format a file count for display.
+///
+/// # Arguments
+///
+/// * `done` - The number of files downloaded.
+/// * `total` - The number of files to download.
+/// * `bytes` - The bytes downloaded.
+fn format_files(done: usize, total: usize, bytes: u64) -> String {
+    format!("  [{}/{}] {}", done, total, format_size(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_files() {
+        assert_eq!(format_files(3, 10, 2048), "  [3/10] 2.0 KiB");
+    }
+}
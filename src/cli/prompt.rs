@@ -0,0 +1,89 @@
+//! Ask the user questions on the terminal.
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Check whether there is a terminal to ask questions on.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Ask a yes or no question.
+///
+/// # Arguments
+///
+/// * `question` - The question, without the `[y/N]` suffix.
+/// * `default` - The answer used when the user just presses enter.
+pub fn confirm(question: &str, default: bool) -> std::io::Result<bool> {
+    let suffix: &str = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        let answer: String = ask(&format!("{} {} ", question, suffix))?;
+        match parse_bool(&answer) {
+            Some(answer) => return Ok(answer),
+            None if answer.is_empty() => return Ok(default),
+            None => continue,
+        }
+    }
+}
+
+/// Ask for a line of text.
+///
+/// # Arguments
+///
+/// * `question` - The question.
+/// * `default` - The answer used when the user just presses enter, shown
+///   in brackets.
+pub fn input(question: &str, default: Option<&str>) -> std::io::Result<String> {
+    let answer: String = match default {
+        Some(default) => ask(&format!("{} [{}]: ", question, default))?,
+        None => ask(&format!("{}: ", question))?,
+    };
+    match default {
+        Some(default) if answer.is_empty() => Ok(default.to_string()),
+        _ => Ok(answer),
+    }
+}
+
+/// Print a prompt and read a line from stdin, without the newline.
+///
+/// Fails if stdin is closed before a line is read.
+///
+/// # Arguments
+///
+/// * `prompt` - The text to print before reading.
+pub fn ask(prompt: &str) -> std::io::Result<String> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut answer: String = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "No answer given",
+        ));
+    }
+    Ok(answer.trim().to_string())
+}
+
+/// Parse a yes or no answer.
+///
+/// # Arguments
+///
+/// * `answer` - The answer, e.g. `y`, `no` or `true`.
+pub fn parse_bool(answer: &str) -> Option<bool> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" | "true" => Some(true),
+        "n" | "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("Y"), Some(true));
+        assert_eq!(parse_bool(" no "), Some(false));
+        assert_eq!(parse_bool(""), None);
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
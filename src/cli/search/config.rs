@@ -26,8 +26,7 @@ impl Config {
 
     /// Get the path to the config file.
     pub fn dir() -> PathBuf {
-        let path = dirs::home_dir().unwrap();
-        path.join(".dev-config/config.yaml")
+        crate::cli::config::Config::dir()
     }
 
     /// Create the config file if it doesn't exist.
@@ -0,0 +1,94 @@
+//! Built-in templates.
+//!
+//! Manifests for the built-in templates are published in the `templates`
+//! directory of the dev-cli repository and fetched at runtime, so they can
+//! change without a new release. A copy is embedded in the binary as a
+//! fallback for when the published manifest cannot be fetched.
+use crate::cli::cache::{self, Mode};
+use crate::cli::template::Manifest;
+
+/// The URL the published built-in manifests are fetched from.
+const MANIFEST_BASE_URL: &str =
+    "https://raw.githubusercontent.com/sam-kenney/dev-cli/main/templates/";
+
+/// The built-in language keys and their embedded manifests.
+const BUILTINS: [(&str, &str); 4] = [
+    ("cpp", include_str!("../../../templates/cpp.yaml")),
+    ("py", include_str!("../../../templates/py.yaml")),
+    ("rb", include_str!("../../../templates/rb.yaml")),
+    ("rs", include_str!("../../../templates/rs.yaml")),
+];
+
+/// Get the built-in language keys.
+pub fn keys() -> impl Iterator<Item = &'static str> {
+    BUILTINS.iter().map(|(key, _)| *key)
+}
+
+/// Get the URL a built-in manifest is published at.
+///
+/// # Arguments
+///
+/// * `key` - The language key, e.g. `py`.
+pub fn manifest_url(key: &str) -> String {
+    format!("{}{}.yaml", MANIFEST_BASE_URL, key)
+}
+
+/// Get the embedded manifest for a built-in language.
+///
+/// # Arguments
+///
+/// * `key` - The language key, e.g. `py`.
+pub fn embedded(key: &str) -> Option<Manifest> {
+    BUILTINS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, contents)| Manifest::from_yaml(contents).expect("Invalid built-in manifest"))
+}
+
+/// Load the manifest for a built-in language.
+///
+/// Prefers the published manifest, falling back to the embedded copy. The
+/// published manifest is only tried once, as the embedded copy will do.
+///
+/// # Arguments
+///
+/// * `key` - The language key, e.g. `py`.
+/// * `mode` - How to use a cached copy of the published manifest.
+pub async fn load(key: &str, mode: Mode) -> Option<Manifest> {
+    let embedded: Manifest = embedded(key)?;
+    let published: Result<Manifest, Box<dyn std::error::Error>> =
+        cache::fetch_manifest(&manifest_url(key), mode, false)
+            .await
+            .and_then(|contents| Ok(Manifest::from_yaml(&contents)?));
+    match published {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            if mode != Mode::Offline {
+                let reason: String = e.to_string();
+                eprintln!(
+                    "Using the built-in `{}` template, the published one could not be fetched: {}",
+                    key,
+                    reason.lines().next().unwrap_or_default()
+                );
+            }
+            Some(embedded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded() {
+        for (key, _) in BUILTINS {
+            assert!(embedded(key).is_some(), "`{}` should parse", key);
+        }
+    }
+
+    #[test]
+    fn test_embedded_unknown() {
+        assert!(embedded("cobol").is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! Conditions that decide which template files are generated.
+//!
+//! A manifest's `when` maps a file or directory to a condition on the
+//! template variables:
+//!
+//! ```yaml
+//! when:
+//!   .github/: ci == github
+//!   .gitlab-ci.yml: ci == gitlab
+//!   .pre-commit-config.yaml: precommit
+//!   docs/: "!minimal"
+//! ```
+//!
+//! A condition is `var` (true if `var` is `true`), `!var`, `var == a|b`
+//! (true if `var` is any of the values) or `var != a|b`.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::cli::template::manifest;
+use crate::cli::template::render::Variables;
+
+/// Evaluate a condition against the template variables.
+///
+/// # Arguments
+///
+/// * `condition` - The condition, e.g. `ci == github|gitlab`.
+/// * `vars` - The template variables.
+pub fn evaluate(condition: &str, vars: &Variables) -> Result<bool, String> {
+    let value = |name: &str| -> Result<String, String> {
+        vars.get(name.trim()).cloned().ok_or_else(|| {
+            format!(
+                "Unknown variable `{}` in condition `{}`",
+                name.trim(),
+                condition
+            )
+        })
+    };
+    let any_of = |values: &str, actual: &str| values.split('|').any(|v| v.trim() == actual);
+
+    if let Some((name, values)) = condition.split_once("!=") {
+        return Ok(!any_of(values, &value(name)?));
+    }
+    if let Some((name, values)) = condition.split_once("==") {
+        return Ok(any_of(values, &value(name)?));
+    }
+    match condition.trim().strip_prefix('!') {
+        Some(name) => Ok(value(name)? != "true"),
+        None => Ok(value(condition)? == "true"),
+    }
+}
+
+/// Get the paths whose condition is false.
+///
+/// Paths must be relative to the template root and stay inside it.
+///
+/// # Arguments
+///
+/// * `when` - The manifest's conditions, keyed by path.
+/// * `vars` - The template variables.
+pub fn excluded(when: &BTreeMap<String, String>, vars: &Variables) -> Result<Vec<String>, String> {
+    let mut excluded: Vec<String> = Vec::new();
+    for (path, condition) in when {
+        if !manifest::is_inside(path) {
+            return Err(format!(
+                "Invalid path `{}` in `when`, paths must be inside the template",
+                path
+            ));
+        }
+        if !evaluate(condition, vars)? {
+            excluded.push(path.clone());
+        }
+    }
+    Ok(excluded)
+}
+
+/// Check whether a path is, or is inside, an excluded path.
+///
+/// # Arguments
+///
+/// * `path` - The path relative to the template root.
+/// * `excluded` - The excluded paths.
+pub fn is_excluded(path: &str, excluded: &[String]) -> bool {
+    excluded
+        .iter()
+        .any(|prefix| Path::new(path).starts_with(prefix.trim_end_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build variables from pairs.
+    fn vars(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let vars: Variables = vars(&[("ci", "gitlab"), ("precommit", "false")]);
+        assert!(evaluate("ci == github|gitlab", &vars).unwrap());
+        assert!(!evaluate("ci == github", &vars).unwrap());
+        assert!(evaluate("ci != none", &vars).unwrap());
+        assert!(!evaluate("precommit", &vars).unwrap());
+        assert!(evaluate("!precommit", &vars).unwrap());
+        assert!(evaluate("docker", &vars).is_err());
+    }
+
+    #[test]
+    fn test_excluded() {
+        let when: BTreeMap<String, String> = BTreeMap::from([
+            (".github/".to_string(), "ci == github".to_string()),
+            (".gitlab-ci.yml".to_string(), "ci == gitlab".to_string()),
+        ]);
+        let excluded: Vec<String> = excluded(&when, &vars(&[("ci", "gitlab")])).unwrap();
+        assert_eq!(excluded, vec![".github/"]);
+        assert!(is_excluded(".github/workflows/ruff.yml", &excluded));
+        assert!(!is_excluded(".gitlab-ci.yml", &excluded));
+        assert!(!is_excluded(".githubx", &excluded));
+
+        let escape: BTreeMap<String, String> =
+            BTreeMap::from([("../x".to_string(), "ci == github".to_string())]);
+        assert!(super::excluded(&escape, &vars(&[("ci", "gitlab")])).is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! Template manifests.
+//!
+//! A manifest describes where a template's files live, which files to
+//! fetch and what to run once they have been written.
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::cli::cache::Mode;
+use crate::cli::template::naming::Identifier;
+use crate::cli::template::questions::Question;
+use crate::cli::template::source::{self, Source};
+
+/// The file name of a manifest inside a template directory.
+pub const MANIFEST_FILE: &str = "dev-template.yaml";
+
+/// A template manifest.
+///
+/// # Fields
+///
+/// * `name` - The display name of the template, e.g. `Python`. Defaults to
+///   the language key.
+/// * `description` - A short description of the template.
+/// * `base_url` - The URL each file path is appended to, or a directory on
+///   disk to copy from as a path or `file://` URL.
+/// * `archive` - A `.tar.gz` or `.zip` archive to extract instead of
+///   fetching files from `base_url`.
+/// * `git` - A Git repository to clone instead of fetching files from
+///   `base_url`.
+/// * `files` - The files to fetch, relative to `base_url`. For a directory,
+///   archive or repository, entries may be directories and an empty list copies
+///   everything.
+/// * `when` - Files or directories only generated when a condition on the
+///   template variables holds, e.g. `.github/: ci == github`.
+/// * `executable` - Files to mark as executable once written, e.g.
+///   `gradlew`, relative to the project root.
+/// * `post` - Steps to run in order once the files have been written.
+/// * `requires` - Programs needed to use the generated project, in
+///   addition to those run by `post`, e.g. `poetry`.
+/// * `questions` - Questions asked to fill in template variables.
+/// * `identifiers` - Identifiers derived from the project name that the
+///   template relies on, checked before anything is written.
+/// * `hooks` - Shell commands to run in order in the project directory
+///   after `post`, e.g. `poetry install`. Template variables are rendered.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub base_url: Option<String>,
+    pub archive: Option<Archive>,
+    pub git: Option<Git>,
+    #[serde(default, deserialize_with = "relative_paths")]
+    pub files: Vec<String>,
+    #[serde(default, deserialize_with = "relative_keys")]
+    pub when: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "relative_paths")]
+    pub executable: Vec<String>,
+    #[serde(default)]
+    pub post: Vec<Step>,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    #[serde(default)]
+    pub questions: Vec<Question>,
+    #[serde(default)]
+    pub identifiers: Vec<Identifier>,
+    #[serde(default)]
+    pub hooks: Vec<String>,
+    #[serde(skip)]
+    pub origin: Option<PathBuf>,
+}
+
+/// Check a path is relative to the template root and stays inside it.
+///
+/// # Arguments
+///
+/// * `path` - The path from the manifest, e.g. `src/main.py`.
+pub fn is_inside(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Check a path from the manifest is inside the template.
+///
+/// # Arguments
+///
+/// * `path` - The path from the manifest.
+fn check_path<E: serde::de::Error>(path: &str) -> Result<(), E> {
+    match is_inside(path) {
+        true => Ok(()),
+        false => Err(E::custom(format!(
+            "Invalid path `{}`, paths must be relative and inside the template",
+            path
+        ))),
+    }
+}
+
+/// Deserialize a list of paths inside the template, such as `files`.
+fn relative_paths<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let paths: Vec<String> = Vec::deserialize(deserializer)?;
+    for path in &paths {
+        check_path(path)?;
+    }
+    Ok(paths)
+}
+
+/// Deserialize a map keyed by paths inside the template, such as `when`.
+fn relative_keys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    let paths: BTreeMap<String, String> = BTreeMap::deserialize(deserializer)?;
+    for path in paths.keys() {
+        check_path(path)?;
+    }
+    Ok(paths)
+}
+
+/// An archive containing a template.
+///
+/// # Fields
+///
+/// * `url` - The URL of the `.tar.gz` or `.zip` archive.
+/// * `subdir` - A directory inside the archive to use as the template root.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Archive {
+    pub url: String,
+    pub subdir: Option<String>,
+}
+
+/// A Git repository containing a template.
+///
+/// # Fields
+///
+/// * `url` - The URL of the repository.
+/// * `ref` - The branch, tag or commit to use. Defaults to the remote `HEAD`.
+/// * `subdir` - A directory inside the repository to use as the template root.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Git {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    pub subdir: Option<String>,
+}
+
+/// A step run after a template's files have been written.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    /// Create a Python virtual environment in `venv`.
+    Virtualenv,
+    /// Initialise a Git repository.
+    GitInit,
+    /// Initialise a Cargo package.
+    CargoInit,
+}
+
+/// Implement the `Step` enum.
+impl Step {
+    /// Check whether the step's output still works after the project
+    /// directory is moved.
+    ///
+    /// A virtual environment records its absolute path, so it must be
+    /// created where the project will live.
+    pub fn is_relocatable(self) -> bool {
+        !matches!(self, Step::Virtualenv)
+    }
+
+    /// Get the program the step runs.
+    pub fn program(self) -> &'static str {
+        match self {
+            Step::Virtualenv => "python3",
+            Step::GitInit => "git",
+            Step::CargoInit => "cargo",
+        }
+    }
+}
+
+/// Implement the `Manifest` struct.
+impl Manifest {
+    /// Parse a manifest from YAML.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The YAML to parse.
+    pub fn from_yaml(contents: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(contents)
+    }
+
+    /// Load a manifest from a URL or a path on disk.
+    ///
+    /// Manifests loaded from a URL are cached for offline use.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - An `http(s)://` URL or a file path.
+    /// * `mode` - How to use the cache for a URL.
+    pub async fn load(location: &str, mode: Mode) -> Result<Self, Box<dyn std::error::Error>> {
+        if source::is_url(location) {
+            let contents: String = crate::cli::cache::fetch_manifest(location, mode, true).await?;
+            return Ok(Manifest::from_yaml(&contents)?);
+        }
+
+        let path: &Path = Path::new(location.strip_prefix("file://").unwrap_or(location));
+        let contents: String = tokio::fs::read_to_string(path).await?;
+        let mut manifest: Manifest = Manifest::from_yaml(&contents)?;
+        manifest.origin = path.parent().map(Path::to_path_buf);
+        Ok(manifest)
+    }
+
+    /// Create a manifest for a template directory on disk.
+    ///
+    /// Uses the directory's `dev-template.yaml` if it has one, defaulting
+    /// `base_url` to the directory. Otherwise the whole directory is copied
+    /// and a Git repository initialised. Files always come from the
+    /// directory, so any `archive` or `git` source in its manifest is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The template directory.
+    /// * `default_name` - The name to use if the manifest has none. Defaults
+    ///   to the name of the directory.
+    pub async fn from_directory(
+        dir: &Path,
+        default_name: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if !dir.is_dir() {
+            return Err(format!("Template directory {} does not exist", dir.display()).into());
+        }
+        let dir: PathBuf = dir.canonicalize()?;
+
+        let path: PathBuf = dir.join(MANIFEST_FILE);
+        let mut manifest: Manifest = if path.exists() {
+            Manifest::load(&path.to_string_lossy(), Mode::Offline).await?
+        } else {
+            Manifest::from_yaml("post: [git-init]")?
+        };
+        manifest.archive = None;
+        manifest.git = None;
+        if manifest.base_url.is_none() {
+            manifest.base_url = Some(dir.to_string_lossy().to_string());
+        }
+        if manifest.name.is_empty() {
+            manifest.name = match default_name {
+                Some(name) => name.to_string(),
+                None => dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            };
+        }
+        manifest.origin = Some(dir);
+        Ok(manifest)
+    }
+
+    /// Get the programs run while generating the template.
+    ///
+    /// Includes `git` for a Git template, then the programs run by `post`,
+    /// without duplicates.
+    pub fn programs(&self) -> Vec<String> {
+        let mut programs: Vec<String> = Vec::new();
+        if self.git.is_some() {
+            programs.push("git".to_string());
+        }
+        for step in &self.post {
+            if !programs.iter().any(|program| program == step.program()) {
+                programs.push(step.program().to_string());
+            }
+        }
+        programs
+    }
+
+    /// Get the programs needed to generate and use the template.
+    ///
+    /// Includes the programs run while generating the template, then
+    /// `requires`, without duplicates.
+    pub fn toolchain(&self) -> Vec<String> {
+        let mut toolchain: Vec<String> = self.programs();
+        toolchain.extend(self.requires.iter().cloned());
+
+        let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+        toolchain.retain(|program| seen.insert(program.clone()));
+        toolchain
+    }
+
+    /// Get where the template's files are fetched from.
+    ///
+    /// A Git repository or archive takes precedence over `base_url`.
+    /// Relative paths are resolved against the directory the manifest was
+    /// loaded from.
+    pub fn source(&self) -> Option<Source> {
+        if let Some(git) = &self.git {
+            return Some(Source::Git {
+                url: git.url.clone(),
+                reference: git.reference.clone(),
+                subdir: git.subdir.clone(),
+            });
+        }
+        if let Some(archive) = &self.archive {
+            return Some(Source::Archive {
+                url: archive.url.clone(),
+                subdir: archive.subdir.clone(),
+            });
+        }
+
+        let source: Source = Source::parse(self.base_url.as_ref()?);
+        match &self.origin {
+            Some(origin) => Some(source.relative_to(origin)),
+            None => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_yaml() {
+        let manifest: Manifest = Manifest::from_yaml(
+            "name: Test\nbase_url: https://example.com/\nfiles:\n  - a.txt\npost:\n  - git-init\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.name, "Test");
+        assert_eq!(manifest.base_url.as_deref(), Some("https://example.com/"));
+        assert_eq!(manifest.files, vec!["a.txt"]);
+        assert_eq!(manifest.post, vec![Step::GitInit]);
+    }
+
+    #[test]
+    fn test_from_yaml_archive() {
+        let manifest: Manifest = Manifest::from_yaml(
+            "base_url: https://example.com/\narchive:\n  url: https://example.com/t.tar.gz\n  subdir: py\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.source(),
+            Some(Source::Archive {
+                url: "https://example.com/t.tar.gz".to_string(),
+                subdir: Some("py".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_yaml_git() {
+        let manifest: Manifest =
+            Manifest::from_yaml("git:\n  url: git@example.com:t/py.git\n  ref: v1.2.0\n").unwrap();
+        assert_eq!(
+            manifest.source(),
+            Some(Source::Git {
+                url: "git@example.com:t/py.git".to_string(),
+                reference: Some("v1.2.0".to_string()),
+                subdir: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_yaml_paths() {
+        for field in ["files", "executable"] {
+            for path in ["/tmp/secret.txt", "../x", "src/../../x", ""] {
+                let yaml: String = format!("{}: [\"{}\"]\n", field, path);
+                assert!(Manifest::from_yaml(&yaml).is_err(), "{}", yaml);
+            }
+        }
+        assert!(Manifest::from_yaml("when:\n  /etc/: ci\n").is_err());
+        assert!(Manifest::from_yaml("files: [src/main.py, .github/]\n").is_ok());
+    }
+
+    #[test]
+    fn test_from_yaml_defaults() {
+        let manifest: Manifest = Manifest::from_yaml("name: Empty\n").unwrap();
+        assert!(manifest.base_url.is_none());
+        assert!(manifest.files.is_empty());
+        assert!(manifest.executable.is_empty());
+        assert!(manifest.post.is_empty());
+        assert!(manifest.hooks.is_empty());
+        assert!(manifest.questions.is_empty());
+    }
+
+    #[test]
+    fn test_from_yaml_when() {
+        let manifest: Manifest = Manifest::from_yaml(
+            "when:\n  .github/: ci == github\n  .pre-commit-config.yaml: precommit\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.when[".github/"], "ci == github");
+        assert_eq!(manifest.when.len(), 2);
+    }
+
+    #[test]
+    fn test_toolchain() {
+        let manifest: Manifest =
+            Manifest::from_yaml("post: [virtualenv, git-init]\nrequires: [poetry, git]\n").unwrap();
+        assert_eq!(manifest.programs(), vec!["python3", "git"]);
+        assert_eq!(manifest.toolchain(), vec!["python3", "git", "poetry"]);
+    }
+
+    #[test]
+    fn test_from_yaml_hooks() {
+        let manifest: Manifest =
+            Manifest::from_yaml("hooks:\n  - poetry install\n  - pre-commit install\n").unwrap();
+        assert_eq!(manifest.hooks, vec!["poetry install", "pre-commit install"]);
+    }
+
+    #[test]
+    fn test_from_yaml_executable() {
+        let manifest: Manifest = Manifest::from_yaml(
+            "files: [gradlew, build.gradle]
+executable: [gradlew]
+",
+        )
+        .unwrap();
+        assert_eq!(manifest.executable, vec!["gradlew"]);
+    }
+
+    #[test]
+    fn test_from_yaml_unknown_step() {
+        assert!(Manifest::from_yaml("name: Bad\npost:\n  - reboot\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_from_path() {
+        let path: String = "_test_load_manifest.yaml".to_string();
+        std::fs::write(&path, "name: Local\n").unwrap();
+        let manifest: Manifest = Manifest::load(&path, Mode::Offline).await.unwrap();
+        assert_eq!(manifest.name, "Local");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_from_directory() {
+        let dir: &Path = Path::new("_test_from_directory");
+        std::fs::create_dir_all(dir).unwrap();
+        let abs: PathBuf = dir.canonicalize().unwrap();
+
+        let manifest: Manifest = Manifest::from_directory(dir, None).await.unwrap();
+        assert_eq!(manifest.name, "_test_from_directory");
+        assert_eq!(manifest.source(), Some(Source::Local(abs.clone())));
+        assert_eq!(manifest.post, vec![Step::GitInit]);
+
+        std::fs::write(dir.join(MANIFEST_FILE), "name: Dir\nbase_url: files\n").unwrap();
+        let manifest: Manifest = Manifest::from_directory(dir, Some("Default"))
+            .await
+            .unwrap();
+        assert_eq!(manifest.name, "Dir");
+        assert_eq!(manifest.source(), Some(Source::Local(abs.join("files"))));
+        assert!(manifest.post.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
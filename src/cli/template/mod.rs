@@ -0,0 +1,11 @@
+pub mod builtin;
+pub mod conditions;
+mod manifest;
+pub mod naming;
+pub mod questions;
+pub mod registry;
+pub mod render;
+mod source;
+
+pub use manifest::{is_inside, Manifest, Step, MANIFEST_FILE};
+pub use source::{git_url, is_git_url, is_url, name_from_url, Source};
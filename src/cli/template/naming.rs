@@ -0,0 +1,256 @@
+//! Project name validation and identifiers derived from it.
+//!
+//! Every project gets `module_name`, `constant_name`, `crate_name` and
+//! `namespace` variables derived from its name. A manifest lists the
+//! identifiers its template relies on, which are checked before anything
+//! is written:
+//!
+//! ```yaml
+//! identifiers: [python-module]
+//! ```
+use serde::Deserialize;
+
+use crate::cli::template::render::{self, Variables};
+
+/// Python keywords, which cannot be module names.
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Rust keywords, including reserved ones, which Cargo rejects as crate names.
+const RUST_KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+/// C++ keywords, which cannot be namespace names.
+#[rustfmt::skip]
+const CPP_KEYWORDS: [&str; 62] = [
+    "alignas", "alignof", "and", "asm", "auto", "bool", "break", "case", "catch", "char", "class",
+    "const", "constexpr", "continue", "decltype", "default", "delete", "do", "double", "else",
+    "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if",
+    "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "nullptr",
+    "operator", "or", "private", "protected", "public", "return", "short", "signed", "sizeof",
+    "static", "struct", "switch", "template", "this", "throw", "true", "try", "typedef", "union",
+    "unsigned", "using", "virtual", "void",
+];
+
+/// An identifier derived from the project name.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Identifier {
+    /// A Python module in `snake_case`, e.g. `my_app`.
+    PythonModule,
+    /// A Ruby constant in `PascalCase`, e.g. `MyApp`.
+    RubyConstant,
+    /// A Cargo crate name in `kebab-case`, e.g. `my-app`.
+    RustCrate,
+    /// A C++ namespace in `snake_case`, e.g. `my_app`.
+    CppNamespace,
+}
+
+/// Implement the `Identifier` enum.
+impl Identifier {
+    /// Every identifier, in the order they are derived.
+    pub const ALL: [Identifier; 4] = [
+        Identifier::PythonModule,
+        Identifier::RubyConstant,
+        Identifier::RustCrate,
+        Identifier::CppNamespace,
+    ];
+
+    /// Get the template variable the identifier is stored in.
+    pub fn variable(&self) -> &'static str {
+        match self {
+            Identifier::PythonModule => "module_name",
+            Identifier::RubyConstant => "constant_name",
+            Identifier::RustCrate => "crate_name",
+            Identifier::CppNamespace => "namespace",
+        }
+    }
+
+    /// Describe the identifier for error messages.
+    fn describe(&self) -> &'static str {
+        match self {
+            Identifier::PythonModule => "Python module name",
+            Identifier::RubyConstant => "Ruby constant",
+            Identifier::RustCrate => "crate name",
+            Identifier::CppNamespace => "C++ namespace",
+        }
+    }
+
+    /// Derive the identifier from a project name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The project name.
+    pub fn derive(&self, name: &str) -> String {
+        match self {
+            Identifier::PythonModule | Identifier::CppNamespace => render::snake_case(name),
+            Identifier::RubyConstant => render::pascal_case(name),
+            Identifier::RustCrate => render::kebab_case(name),
+        }
+    }
+
+    /// Check a value is a valid identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The identifier to check.
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let (keywords, rest): (&[&str], fn(char) -> bool) = match self {
+            Identifier::PythonModule => (&PYTHON_KEYWORDS, |c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'
+            }),
+            Identifier::RubyConstant => (&[], |c| c.is_ascii_alphanumeric() || c == '_'),
+            Identifier::RustCrate => (&RUST_KEYWORDS, |c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'
+            }),
+            Identifier::CppNamespace => (&CPP_KEYWORDS, |c| c.is_ascii_alphanumeric() || c == '_'),
+        };
+        let first: Option<char> = value.chars().next();
+        let starts: bool = match self {
+            Identifier::RubyConstant => first.is_some_and(|c| c.is_ascii_uppercase()),
+            _ => first.is_some_and(|c| c.is_ascii_alphabetic() || c == '_'),
+        };
+
+        let problem: Option<&str> = if value.is_empty() {
+            Some("it is empty")
+        } else if !starts {
+            Some(match self {
+                Identifier::RubyConstant => "it must start with an uppercase letter",
+                _ => "it must start with a letter or underscore",
+            })
+        } else if !value.chars().all(rest) {
+            Some("it contains characters that are not allowed")
+        } else if keywords.contains(&value) {
+            Some("it is a reserved keyword")
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => Err(format!(
+                "`{}` is not a valid {}, {}",
+                value,
+                self.describe(),
+                problem
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check a project name can be used as a directory name.
+///
+/// # Arguments
+///
+/// * `name` - The project name.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let problem: Option<&str> = if name.is_empty() {
+        Some("it is empty")
+    } else if name.contains(['/', '\\']) {
+        Some("it must not contain path separators, use `--path` to choose the directory")
+    } else if name.chars().any(char::is_whitespace) {
+        Some("it must not contain whitespace")
+    } else if !name.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        Some("it must start with a letter, digit or underscore")
+    } else if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        Some("it may only contain letters, digits, `-`, `_` and `.`")
+    } else {
+        None
+    };
+    match problem {
+        Some(problem) => Err(format!("Invalid project name `{}`, {}", name, problem)),
+        None => Ok(()),
+    }
+}
+
+/// Derive every identifier from a project name.
+///
+/// # Arguments
+///
+/// * `name` - The project name.
+pub fn identifiers(name: &str) -> Variables {
+    Identifier::ALL
+        .iter()
+        .map(|identifier| (identifier.variable().to_string(), identifier.derive(name)))
+        .collect()
+}
+
+/// Check the identifiers a template relies on.
+///
+/// # Arguments
+///
+/// * `required` - The identifiers the template relies on.
+/// * `vars` - The template variables, holding each identifier.
+pub fn check(required: &[Identifier], vars: &Variables) -> Result<(), String> {
+    for identifier in required {
+        let value: &str = vars
+            .get(identifier.variable())
+            .map(String::as_str)
+            .unwrap_or_default();
+        identifier.validate(value).map_err(|e| {
+            format!(
+                "{}, pass `--var {}=NAME` to choose one",
+                e,
+                identifier.variable()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("my-app").is_ok());
+        assert!(validate_name("MyApp_2.0").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("my app").is_err());
+        assert!(validate_name("path/to/app").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("-app").is_err());
+        assert!(validate_name("app!").is_err());
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let vars: Variables = identifiers("my-HTTP-app");
+        assert_eq!(vars["module_name"], "my_http_app");
+        assert_eq!(vars["constant_name"], "MyHttpApp");
+        assert_eq!(vars["crate_name"], "my-http-app");
+        assert_eq!(vars["namespace"], "my_http_app");
+    }
+
+    #[test]
+    fn test_validate_identifier() {
+        assert!(Identifier::PythonModule.validate("my_app").is_ok());
+        assert!(Identifier::PythonModule.validate("2fast").is_err());
+        assert!(Identifier::PythonModule.validate("class").is_err());
+        assert!(Identifier::RubyConstant.validate("MyApp").is_ok());
+        assert!(Identifier::RubyConstant.validate("myApp").is_err());
+        assert!(Identifier::RustCrate.validate("my-app").is_ok());
+        assert!(Identifier::RustCrate.validate("self").is_err());
+        assert!(Identifier::CppNamespace.validate("namespace").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let vars: Variables = identifiers("class");
+        assert!(check(&[Identifier::RustCrate], &vars).is_ok());
+        let error: String = check(&[Identifier::PythonModule], &vars).unwrap_err();
+        assert!(error.contains("--var module_name=NAME"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Questions a template asks to fill in its variables.
+//!
+//! Answers come from `--var`, then an answers file, then an interactive
+//! prompt on a terminal, then the question's default.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::cli::prompt;
+use crate::cli::template::render::{self, Variables};
+
+/// The file answers are recorded in inside a generated project.
+pub const ANSWERS_FILE: &str = ".dev-answers.yaml";
+
+/// Answers to a template's questions, keyed by variable name.
+pub type Answers = BTreeMap<String, String>;
+
+/// A question a template asks.
+///
+/// ```yaml
+/// questions:
+///   - name: description
+///     prompt: Short description
+///     validate: ".+"
+///   - name: license
+///     type: choice
+///     choices: [MIT, Apache-2.0]
+///     default: MIT
+///   - name: docker
+///     type: bool
+///     default: false
+/// ```
+///
+/// # Fields
+///
+/// * `name` - The variable the answer is stored in.
+/// * `prompt` - The text shown when asking. Defaults to `name`.
+/// * `kind` - The type of answer expected.
+/// * `default` - The answer used if none is given, rendered with the other
+///   variables, e.g. `"{{ project_name | snake_case }}"`.
+/// * `choices` - The accepted answers for a `choice` question.
+/// * `validate` - A regex a `string` answer must match in full.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Question {
+    pub name: String,
+    pub prompt: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: Kind,
+    pub default: Option<serde_yaml::Value>,
+    #[serde(default)]
+    pub choices: Vec<String>,
+    pub validate: Option<String>,
+}
+
+/// The type of answer a question expects.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// Free text.
+    #[default]
+    String,
+    /// `true` or `false`.
+    Bool,
+    /// One of `choices`.
+    Choice,
+}
+
+/// Implement the `Question` struct.
+impl Question {
+    /// Get the question's default answer, rendered with `vars`.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - The variables known so far.
+    fn default_answer(&self, vars: &Variables) -> Option<String> {
+        let default: String = match self.default.as_ref()? {
+            serde_yaml::Value::String(value) => render::render(value, vars),
+            serde_yaml::Value::Bool(value) => value.to_string(),
+            serde_yaml::Value::Number(value) => value.to_string(),
+            _ => return None,
+        };
+        Some(default)
+    }
+
+    /// Check an answer, returning it in its normal form.
+    ///
+    /// # Arguments
+    ///
+    /// * `answer` - The answer to check.
+    pub fn validate(&self, answer: &str) -> Result<String, String> {
+        match self.kind {
+            Kind::Bool => prompt::parse_bool(answer)
+                .map(|answer| answer.to_string())
+                .ok_or_else(|| format!("`{}` must be yes or no, got `{}`", self.name, answer)),
+            Kind::Choice if !self.choices.iter().any(|choice| choice == answer) => Err(format!(
+                "`{}` must be one of {}, got `{}`",
+                self.name,
+                self.choices.join(", "),
+                answer
+            )),
+            Kind::Choice => Ok(answer.to_string()),
+            Kind::String => {
+                if let Some(pattern) = &self.validate {
+                    let regex: regex::Regex = regex::Regex::new(&format!("^(?:{})$", pattern))
+                        .map_err(|e| format!("Invalid regex for `{}`: {}", self.name, e))?;
+                    if !regex.is_match(answer) {
+                        return Err(format!(
+                            "`{}` must match `{}`, got `{}`",
+                            self.name, pattern, answer
+                        ));
+                    }
+                }
+                Ok(answer.to_string())
+            }
+        }
+    }
+
+    /// Ask the question on the terminal until a valid answer is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - The answer used if the user just presses enter.
+    fn ask(&self, default: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+        let text: &str = self.prompt.as_deref().unwrap_or(&self.name);
+        loop {
+            let answer: String = match self.kind {
+                Kind::Bool => prompt::confirm(text, default == Some("true"))?.to_string(),
+                Kind::Choice => {
+                    let text: String = format!("{} ({})", text, self.choices.join("/"));
+                    prompt::input(&text, default)?
+                }
+                Kind::String => prompt::input(text, default)?,
+            };
+            match self.validate(&answer) {
+                Ok(answer) => return Ok(answer),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+}
+
+/// Answer a template's questions and add the answers to `vars`.
+///
+/// Returns the answers so they can be recorded in the project.
+///
+/// # Arguments
+///
+/// * `questions` - The template's questions.
+/// * `given` - Answers from `--var` and the answers file.
+/// * `vars` - The template variables, updated with each answer.
+/// * `interactive` - Whether unanswered questions may be asked.
+pub fn answer(
+    questions: &[Question],
+    given: &Answers,
+    vars: &mut Variables,
+    interactive: bool,
+) -> Result<Answers, Box<dyn std::error::Error>> {
+    let mut answers: Answers = Answers::new();
+    for question in questions {
+        let default: Option<String> = question.default_answer(vars);
+        let answer: String = match (given.get(&question.name), &default) {
+            (Some(answer), _) => question.validate(answer)?,
+            (None, _) if interactive => question.ask(default.as_deref())?,
+            (None, Some(default)) => question.validate(default)?,
+            (None, None) => {
+                return Err(format!(
+                    "No answer for `{}`, pass `--var {}=VALUE` or `--answers FILE`",
+                    question.name, question.name
+                )
+                .into())
+            }
+        };
+        vars.insert(question.name.clone(), answer.clone());
+        answers.insert(question.name.clone(), answer);
+    }
+    Ok(answers)
+}
+
+/// Load answers from a YAML file of `name: value` pairs.
+///
+/// # Arguments
+///
+/// * `path` - The answers file, e.g. a previous project's `.dev-answers.yaml`.
+pub fn load_answers(path: &Path) -> Result<Answers, Box<dyn std::error::Error>> {
+    let contents: String = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read answers {}: {}", path.display(), e))?;
+    let values: BTreeMap<String, serde_yaml::Value> = match contents.trim() {
+        "" => BTreeMap::new(),
+        _ => serde_yaml::from_str(&contents)?,
+    };
+
+    values
+        .into_iter()
+        .map(|(name, value)| match value {
+            serde_yaml::Value::String(value) => Ok((name, value)),
+            serde_yaml::Value::Bool(value) => Ok((name, value.to_string())),
+            serde_yaml::Value::Number(value) => Ok((name, value.to_string())),
+            _ => Err(format!("Answer `{}` in {} must be a scalar", name, path.display()).into()),
+        })
+        .collect()
+}
+
+/// Record answers in a generated project for later reuse.
+///
+/// # Arguments
+///
+/// * `dir` - The project directory.
+/// * `answers` - The answers to record.
+pub fn write_answers(dir: &Path, answers: &Answers) -> Result<(), Box<dyn std::error::Error>> {
+    let contents: String = format!(
+        "# Answers used to generate this project.\n\
+         # Reuse them with `dev project new <name> --answers {}`.\n{}",
+        ANSWERS_FILE,
+        serde_yaml::to_string(answers)?
+    );
+    std::fs::write(dir.join(ANSWERS_FILE), contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse questions from YAML.
+    fn questions(yaml: &str) -> Vec<Question> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let qs: Vec<Question> = questions(
+            "- {name: docker, type: bool}
+- {name: license, type: choice, choices: [MIT, Apache-2.0]}
+- {name: module, validate: '[a-z_]+'}
+",
+        );
+        assert_eq!(qs[0].validate("Yes").unwrap(), "true");
+        assert!(qs[0].validate("maybe").is_err());
+        assert_eq!(qs[1].validate("MIT").unwrap(), "MIT");
+        assert!(qs[1].validate("GPL").is_err());
+        assert_eq!(qs[2].validate("my_app").unwrap(), "my_app");
+        assert!(qs[2].validate("my-app").is_err());
+    }
+
+    #[test]
+    fn test_answer_non_interactive() {
+        let qs: Vec<Question> = questions(
+            "- {name: module, default: '{{ project_name | snake_case }}'}
+- {name: docker, type: bool, default: false}
+",
+        );
+        let mut vars: Variables = Variables::new();
+        vars.insert("project_name".to_string(), "my-app".to_string());
+        let given: Answers = Answers::from([("docker".to_string(), "y".to_string())]);
+
+        let answers: Answers = answer(&qs, &given, &mut vars, false).unwrap();
+        assert_eq!(answers["module"], "my_app");
+        assert_eq!(answers["docker"], "true");
+        assert_eq!(vars["docker"], "true");
+    }
+
+    #[test]
+    fn test_answer_missing() {
+        let qs: Vec<Question> = questions("- {name: description}\n");
+        let mut vars: Variables = Variables::new();
+        assert!(answer(&qs, &Answers::new(), &mut vars, false).is_err());
+    }
+
+    #[test]
+    fn test_answers_round_trip() {
+        let dir: &Path = Path::new("_test_answers_round_trip");
+        std::fs::create_dir_all(dir).unwrap();
+        let answers: Answers = Answers::from([
+            ("docker".to_string(), "true".to_string()),
+            ("license".to_string(), "MIT".to_string()),
+        ]);
+        write_answers(dir, &answers).unwrap();
+        assert_eq!(load_answers(&dir.join(ANSWERS_FILE)).unwrap(), answers);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! The language registry.
+//!
+//! Maps the keys accepted by `--lang` to template manifests, checking
+//! languages defined in the config file before the built-in ones.
+use crate::cli::cache::Mode;
+use crate::cli::config::{Config, Language};
+use crate::cli::template::{builtin, Manifest};
+
+/// Other names accepted by `--lang` for the built-in languages.
+const ALIASES: [(&str, &str); 4] = [
+    ("c++", "cpp"),
+    ("python", "py"),
+    ("ruby", "rb"),
+    ("rust", "rs"),
+];
+
+/// How similar an unknown key must be to a known one to suggest it.
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// Get every registered language key, sorted.
+///
+/// # Arguments
+///
+/// * `config` - The loaded config.
+pub fn keys(config: &Config) -> Vec<String> {
+    let mut keys: Vec<String> = config
+        .languages
+        .keys()
+        .cloned()
+        .chain(builtin::keys().map(String::from))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Resolve a `--lang` value to a registered language key.
+///
+/// Keys in the config file take precedence over aliases, so a config can
+/// register its own `python` language.
+///
+/// # Arguments
+///
+/// * `lang` - The value passed to `--lang`, e.g. `python`.
+/// * `config` - The loaded config.
+pub fn resolve(lang: &str, config: &Config) -> Result<String, String> {
+    let keys: Vec<String> = keys(config);
+    if keys.iter().any(|key| key == lang) {
+        return Ok(lang.to_string());
+    }
+    let alias: Option<&str> = ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(lang))
+        .map(|(_, key)| *key);
+    if let Some(key) = alias.filter(|key| keys.iter().any(|k| k == key)) {
+        return Ok(key.to_string());
+    }
+
+    let mut message: String = format!("Language `{}` not yet supported", lang);
+    if let Some(suggestion) = suggest(lang, &keys) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    message.push_str(&format!(
+        "\n  Available languages: {}\n  Run `dev project list-langs` for details",
+        keys.join(", ")
+    ));
+    Err(message)
+}
+
+/// Find the registered key or alias closest to an unknown key.
+///
+/// # Arguments
+///
+/// * `lang` - The unknown key.
+/// * `keys` - The registered keys.
+fn suggest(lang: &str, keys: &[String]) -> Option<String> {
+    let aliases = ALIASES
+        .iter()
+        .filter(|(_, key)| keys.iter().any(|k| k == key))
+        .map(|(alias, _)| alias.to_string());
+    keys.iter()
+        .cloned()
+        .chain(aliases)
+        .map(|key| (strsim::jaro_winkler(&lang.to_lowercase(), &key), key))
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, key)| key)
+}
+
+/// Load the manifest registered for a language.
+///
+/// Returns `Ok(None)` if no language is registered under `key`.
+///
+/// # Arguments
+///
+/// * `key` - The language key, e.g. `py`.
+/// * `config` - The loaded config.
+/// * `mode` - How to use cached copies of remote manifests.
+pub async fn load(
+    key: &str,
+    config: &Config,
+    mode: Mode,
+) -> Result<Option<Manifest>, Box<dyn std::error::Error>> {
+    let manifest: Option<Manifest> = match config.languages.get(key) {
+        Some(Language::Manifest { manifest }) => {
+            let location: String = Config::resolve(manifest);
+            let manifest: Manifest = Manifest::load(&location, mode)
+                .await
+                .map_err(|e| format!("Failed to load manifest {}: {}", location, e))?;
+            Some(manifest)
+        }
+        Some(Language::Inline(manifest)) => Some(manifest.as_ref().clone()),
+        None => builtin::load(key, mode).await,
+    };
+
+    Ok(manifest.map(|mut manifest| {
+        if manifest.name.is_empty() {
+            manifest.name = key.to_string();
+        }
+        manifest
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_inline() {
+        let config: Config =
+            Config::from_yaml("languages:\n  internal-svc:\n    files: [a.txt]\n").unwrap();
+        let manifest: Manifest = load("internal-svc", &config, Mode::Offline)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.name, "internal-svc");
+        assert_eq!(manifest.files, vec!["a.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_load_from_path() {
+        let path: String = format!("{}/_test_registry.yaml", crate::cli::utils::current_dir());
+        std::fs::write(&path, "name: Svc\n").unwrap();
+        let config: Config =
+            Config::from_yaml(&format!("languages:\n  svc:\n    manifest: {}\n", path)).unwrap();
+        let manifest: Manifest = load("svc", &config, Mode::Offline).await.unwrap().unwrap();
+        assert_eq!(manifest.name, "Svc");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_keys() {
+        let config: Config =
+            Config::from_yaml("languages:\n  go:\n    files: []\n  py:\n    files: []\n").unwrap();
+        assert_eq!(keys(&config), vec!["cpp", "go", "py", "rb", "rs"]);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let config: Config = Config::from_yaml("languages:\n  python:\n    files: []\n").unwrap();
+        assert_eq!(resolve("py", &config).unwrap(), "py");
+        assert_eq!(resolve("Ruby", &config).unwrap(), "rb");
+        assert_eq!(resolve("c++", &config).unwrap(), "cpp");
+        assert_eq!(resolve("python", &config).unwrap(), "python");
+
+        let error: String = resolve("pyhton", &config).unwrap_err();
+        assert!(error.contains("did you mean `python`?"));
+        assert!(error.contains("cpp, py, python, rb, rs"));
+        assert!(!resolve("haskell", &config)
+            .unwrap_err()
+            .contains("did you mean"));
+    }
+
+    #[tokio::test]
+    async fn test_load_unknown() {
+        let config: Config = Config::default();
+        assert!(load("cobol", &config, Mode::Offline)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,322 @@
+//! Template variable substitution.
+//!
+//! Replaces `{{ variable }}` and `{{ variable | filter }}` in file contents
+//! and paths. Anything that is not a known variable, such as GitHub Actions
+//! `${{ matrix.os }}` expressions, is left untouched.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Variables available to templates.
+pub type Variables = HashMap<String, String>;
+
+/// Build the variables for a project.
+///
+/// Provides `project_name`, `author`, `email`, `year` and the identifiers
+/// derived from the name, which are overridden by any `key=value` pairs
+/// passed with `--var`.
+///
+/// # Arguments
+///
+/// * `name` - The name of the project.
+/// * `vars` - `key=value` pairs from the command line.
+pub fn variables(name: &str, vars: &[String]) -> Result<Variables, String> {
+    use crate::cli::bash;
+    use crate::cli::template::naming;
+
+    let mut variables: Variables = HashMap::new();
+    variables.insert("project_name".to_string(), name.to_string());
+    variables.insert(
+        "author".to_string(),
+        bash::git_config("user.name").unwrap_or_default(),
+    );
+    variables.insert(
+        "email".to_string(),
+        bash::git_config("user.email").unwrap_or_default(),
+    );
+    variables.insert("year".to_string(), current_year().to_string());
+    variables.extend(naming::identifiers(name));
+
+    for var in vars {
+        let (key, value) = parse_var(var)?;
+        variables.insert(key, value);
+    }
+    Ok(variables)
+}
+
+/// Parse a `key=value` pair.
+///
+/// # Arguments
+///
+/// * `var` - The pair to parse.
+pub fn parse_var(var: &str) -> Result<(String, String), String> {
+    match var.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("Invalid variable `{}`, expected KEY=VALUE", var)),
+    }
+}
+
+/// Render a string, replacing known variables.
+///
+/// # Arguments
+///
+/// * `text` - The text to render.
+/// * `vars` - The variables to substitute.
+pub fn render(text: &str, vars: &Variables) -> String {
+    let mut out: String = String::with_capacity(text.len());
+    let mut rest: &str = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let expr: &str = &rest[start + 2..start + 2 + len];
+        let end: usize = start + 2 + len + 2;
+
+        out.push_str(&rest[..start]);
+        match evaluate(expr, vars) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Evaluate the expression inside `{{ }}`.
+///
+/// Returns `None` if the variable or any filter is unknown.
+///
+/// # Arguments
+///
+/// * `expr` - The expression, e.g. `project_name | snake_case`.
+/// * `vars` - The variables to substitute.
+fn evaluate(expr: &str, vars: &Variables) -> Option<String> {
+    let mut parts = expr.split('|').map(str::trim);
+    let mut value: String = vars.get(parts.next()?)?.clone();
+
+    for filter in parts {
+        value = match filter {
+            "snake_case" => snake_case(&value),
+            "kebab_case" => kebab_case(&value),
+            "pascal_case" => pascal_case(&value),
+            "lower" => value.to_lowercase(),
+            "upper" => value.to_uppercase(),
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Render the contents and paths of every file in a directory.
+///
+/// Files that are not valid UTF-8 are renamed but their contents are left
+/// untouched. `.git` directories are skipped.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to render.
+/// * `vars` - The variables to substitute.
+pub fn render_dir(dir: &Path, vars: &Variables) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry: fs::DirEntry = entry?;
+        let file_name: String = entry.file_name().to_string_lossy().to_string();
+        if file_name == ".git" {
+            continue;
+        }
+
+        let rendered: String = render(&file_name, vars);
+        let path: PathBuf = if rendered != file_name {
+            let target: PathBuf = dir.join(&rendered);
+            fs::create_dir_all(target.parent().unwrap())?;
+            fs::rename(entry.path(), &target)?;
+            target
+        } else {
+            entry.path()
+        };
+
+        if path.is_dir() {
+            render_dir(&path, vars)?;
+        } else if let Ok(contents) = fs::read_to_string(&path) {
+            let rendered: String = render(&contents, vars);
+            if rendered != contents {
+                fs::write(&path, rendered)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split an identifier into lowercase words.
+///
+/// Splits on non-alphanumeric characters and on case changes, so
+/// `myHTTPServer`, `my-http-server` and `my_http_server` all give the
+/// same words.
+///
+/// # Arguments
+///
+/// * `value` - The value to split.
+fn words(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        let prev: Option<char> = if i > 0 { Some(chars[i - 1]) } else { None };
+        let next: Option<char> = chars.get(i + 1).copied();
+        let boundary: bool = c.is_uppercase()
+            && match prev {
+                Some(p) if p.is_lowercase() || p.is_numeric() => true,
+                Some(p) if p.is_uppercase() => next.is_some_and(char::is_lowercase),
+                _ => false,
+            };
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Convert a value to `snake_case`.
+///
+/// # Arguments
+///
+/// * `value` - The value to convert.
+pub fn snake_case(value: &str) -> String {
+    words(value).join("_")
+}
+
+/// Convert a value to `kebab-case`.
+///
+/// # Arguments
+///
+/// * `value` - The value to convert.
+pub fn kebab_case(value: &str) -> String {
+    words(value).join("-")
+}
+
+/// Convert a value to `PascalCase`.
+///
+/// # Arguments
+///
+/// * `value` - The value to convert.
+pub fn pascal_case(value: &str) -> String {
+    words(value)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Get the current year in UTC.
+fn current_year() -> i64 {
+    let secs: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // Convert days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z: i64 = (secs / 86_400) as i64 + 719_468;
+    let era: i64 = z.div_euclid(146_097);
+    let doe: i64 = z - era * 146_097;
+    let yoe: i64 = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy: i64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp: i64 = (5 * doy + 2) / 153;
+    let month: i64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    yoe + era * 400 + i64::from(month <= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Variables {
+        let mut vars: Variables = HashMap::new();
+        vars.insert("project_name".to_string(), "my-app".to_string());
+        vars
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("name = \"{{project_name}}\"", &vars()),
+            "name = \"my-app\""
+        );
+        assert_eq!(render("{{ project_name | snake_case }}", &vars()), "my_app");
+        assert_eq!(render("{{ project_name | pascal_case }}", &vars()), "MyApp");
+    }
+
+    #[test]
+    fn test_render_leaves_unknown() {
+        let text: &str = "${{ matrix.os }} {{ missing }} {{ project_name | nope }} {{ open";
+        assert_eq!(render(text, &vars()), text);
+    }
+
+    #[test]
+    fn test_cases() {
+        assert_eq!(snake_case("myHTTPServer"), "my_http_server");
+        assert_eq!(kebab_case("My App 2"), "my-app-2");
+        assert_eq!(pascal_case("my_app"), "MyApp");
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(
+            parse_var("author=Jane=Doe"),
+            Ok(("author".to_string(), "Jane=Doe".to_string()))
+        );
+        assert!(parse_var("author").is_err());
+        assert!(parse_var("=value").is_err());
+    }
+
+    #[test]
+    fn test_variables_override() {
+        let vars: Variables = variables("app", &["year=1999".to_string()]).unwrap();
+        assert_eq!(vars["project_name"], "app");
+        assert_eq!(vars["year"], "1999");
+    }
+
+    #[test]
+    fn test_current_year() {
+        assert!(current_year() >= 2024);
+    }
+
+    #[test]
+    fn test_render_dir() {
+        let dir: &Path = Path::new("_test_render_dir");
+        fs::create_dir_all(dir.join("src/{{project_name | snake_case}}")).unwrap();
+        fs::write(
+            dir.join("src/{{project_name | snake_case}}/__init__.py"),
+            "# {{project_name}}",
+        )
+        .unwrap();
+
+        render_dir(dir, &vars()).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("src/my_app/__init__.py")).unwrap(),
+            "# my-app"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Where a template's files are fetched from.
+use std::path::{Path, PathBuf};
+
+/// The source of a template's files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Files are downloaded by appending each path to a base URL.
+    Remote(String),
+    /// Files are copied from a directory on disk.
+    Local(PathBuf),
+    /// Files are extracted from a `.tar.gz` or `.zip` archive, optionally
+    /// from a directory inside it.
+    Archive { url: String, subdir: Option<String> },
+    /// Files are copied from a shallow clone of a Git repository at a
+    /// branch, tag or commit, optionally from a directory inside it.
+    Git {
+        url: String,
+        reference: Option<String>,
+        subdir: Option<String>,
+    },
+}
+
+/// Implement the `Source` enum.
+impl Source {
+    /// Parse a source from a manifest's `base_url`.
+    ///
+    /// `http(s)://` URLs are remote, `file://` URLs and plain paths are local.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL or path.
+    pub fn parse(base_url: &str) -> Self {
+        if is_url(base_url) {
+            return Source::Remote(base_url.to_string());
+        }
+        let path: &str = base_url.strip_prefix("file://").unwrap_or(base_url);
+        Source::Local(PathBuf::from(path))
+    }
+
+    /// Resolve a relative local source against a directory.
+    ///
+    /// Remote and absolute sources are returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to resolve against.
+    pub fn relative_to(self, dir: &Path) -> Self {
+        match self {
+            Source::Local(path) if path.is_relative() => Source::Local(dir.join(path)),
+            source => source,
+        }
+    }
+}
+
+/// Check whether a location is an HTTP(S) URL.
+///
+/// # Arguments
+///
+/// * `location` - The location to check.
+pub fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Check whether a location looks like a Git repository URL.
+///
+/// Matches `git@host:path`, `ssh://` and `git://` URLs, anything ending in
+/// `.git`, and any URL prefixed with `git+`, e.g. `git+https://host/repo`.
+///
+/// # Arguments
+///
+/// * `location` - The location to check.
+pub fn is_git_url(location: &str) -> bool {
+    location.starts_with("git@")
+        || location.starts_with("git+")
+        || location.starts_with("ssh://")
+        || location.starts_with("git://")
+        || (location.contains("://") && location.trim_end_matches('/').ends_with(".git"))
+}
+
+/// Strip the `git+` prefix from a Git URL.
+///
+/// # Arguments
+///
+/// * `location` - The Git URL.
+pub fn git_url(location: &str) -> &str {
+    location.strip_prefix("git+").unwrap_or(location)
+}
+
+/// Get a template's name from the last segment of its URL.
+///
+/// Any `.git`, `.tar.gz`, `.tgz` or `.zip` extension is removed.
+///
+/// # Arguments
+///
+/// * `location` - The URL, e.g. `git@github.com:org/python-template.git`.
+pub fn name_from_url(location: &str) -> String {
+    let segment: &str = location
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(location);
+    [".git", ".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .find_map(|ext| segment.strip_suffix(ext))
+        .unwrap_or(segment)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Source::parse("https://example.com/t/"),
+            Source::Remote("https://example.com/t/".to_string())
+        );
+        assert_eq!(
+            Source::parse("file:///srv/templates/py"),
+            Source::Local(PathBuf::from("/srv/templates/py"))
+        );
+        assert_eq!(
+            Source::parse("../py"),
+            Source::Local(PathBuf::from("../py"))
+        );
+    }
+
+    #[test]
+    fn test_is_git_url() {
+        assert!(is_git_url("git@github.com:sam-kenney/python-template.git"));
+        assert!(is_git_url(
+            "https://github.com/sam-kenney/python-template.git"
+        ));
+        assert!(is_git_url("git+https://git.example.com/templates/py"));
+        assert!(!is_git_url("https://example.com/t.tar.gz"));
+        assert!(!is_git_url("./templates/py.git"));
+        assert_eq!(git_url("git+file:///srv/t"), "file:///srv/t");
+    }
+
+    #[test]
+    fn test_name_from_url() {
+        assert_eq!(
+            name_from_url("git@github.com:org/py-template.git"),
+            "py-template"
+        );
+        assert_eq!(name_from_url("git+file:///srv/templates/rb/"), "rb");
+        assert_eq!(
+            name_from_url("https://example.com/cpp-template.tar.gz"),
+            "cpp-template"
+        );
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let dir: &Path = Path::new("/srv/templates");
+        assert_eq!(
+            Source::parse("py").relative_to(dir),
+            Source::Local(PathBuf::from("/srv/templates/py"))
+        );
+        assert_eq!(
+            Source::parse("/opt/py").relative_to(dir),
+            Source::Local(PathBuf::from("/opt/py"))
+        );
+    }
+}
@@ -90,6 +90,41 @@ where
     }
 }
 
+/// Access a boolean flag from a subcommand.
+///
+/// # Arguments
+///
+/// * `matches` - The matches from the CLI.
+/// * `command` - The subcommand to access.
+/// * `name` - The name of the flag to access.
+///
+/// # Returns
+///
+/// * `bool` - Whether the flag was set.
+pub fn get_flag(matches: &ArgMatches, command: &str, name: &str) -> bool {
+    matches.subcommand_matches(command).unwrap().get_flag(name)
+}
+
+/// Access every value of a repeatable parameter from a subcommand.
+///
+/// # Arguments
+///
+/// * `matches` - The matches from the CLI.
+/// * `command` - The subcommand to access.
+/// * `name` - The name of the parameter to access.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The values of the parameter, empty if it is not set.
+pub fn get_many_values(matches: &ArgMatches, command: &str, name: &str) -> Vec<String> {
+    matches
+        .subcommand_matches(command)
+        .unwrap()
+        .get_many::<String>(name)
+        .map(|vals| vals.cloned().collect())
+        .unwrap_or_default()
+}
+
 /// Get the current working directory.
 /// # Panics
 /// Panics if the current working directory cannot be determined.
@@ -108,9 +143,70 @@ pub fn current_dir() -> String {
     }
 }
 
+/// A temporary directory that is removed when dropped.
+pub struct TempDir(std::path::PathBuf);
+
+/// Implement the `TempDir` struct.
+impl TempDir {
+    /// Create a new, empty temporary directory inside `parent`.
+    ///
+    /// Useful when the directory will later be renamed into place, which
+    /// only works within a single filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - The directory to create the temporary directory in.
+    /// * `prefix` - A prefix for the directory name.
+    pub fn new_in(parent: &std::path::Path, prefix: &str) -> std::io::Result<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path: std::path::PathBuf = parent.join(format!(
+            "{}-{}-{}",
+            prefix,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)?;
+        Ok(TempDir(path))
+    }
+
+    /// Take ownership of an existing directory, removing it when dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory.
+    pub fn adopt(path: std::path::PathBuf) -> Self {
+        TempDir(path)
+    }
+
+    /// Get the path to the directory.
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+
+    /// Keep the directory instead of removing it when dropped.
+    pub fn keep(self) -> std::path::PathBuf {
+        let path: std::path::PathBuf = self.0.clone();
+        std::mem::forget(self);
+        path
+    }
+}
+
+/// Implement the `Drop` trait for `TempDir`.
+impl Drop for TempDir {
+    /// Remove the directory and everything in it.
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use clap::{Arg, ArgMatches, Command};
+    use clap::{Arg, ArgAction, ArgMatches, Command};
 
     #[test]
     fn test_get_required_value() {
@@ -180,9 +276,55 @@ mod tests {
         assert_eq!(val, "default");
     }
 
+    #[test]
+    fn test_get_flag() {
+        let matches: ArgMatches = Command::new("dev-cli")
+            .subcommand(
+                Command::new("test")
+                    .about("A test subcommand")
+                    .arg(Arg::new("test").long("test").action(ArgAction::SetTrue)),
+            )
+            .get_matches_from(vec!["dev-cli", "test", "--test"]);
+
+        assert!(super::get_flag(&matches, "test", "test"));
+    }
+
+    #[test]
+    fn test_get_many_values() {
+        let matches: ArgMatches = Command::new("dev-cli")
+            .subcommand(
+                Command::new("test")
+                    .about("A test subcommand")
+                    .arg(Arg::new("test").long("test").action(ArgAction::Append)),
+            )
+            .get_matches_from(vec!["dev-cli", "test", "--test", "a", "--test", "b"]);
+
+        let val: Vec<String> = super::get_many_values(&matches, "test", "test");
+        assert_eq!(val, vec!["a", "b"]);
+    }
+
     #[test]
     fn test_current_dir() {
         let dir: String = super::current_dir();
         assert_eq!(dir, std::env::current_dir().unwrap().to_str().unwrap());
     }
+
+    #[test]
+    fn test_temp_dir() {
+        let dir: super::TempDir =
+            super::TempDir::new_in(&std::env::temp_dir(), "dev-cli-test").unwrap();
+        let path: std::path::PathBuf = dir.path().to_path_buf();
+        assert!(path.is_dir());
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_temp_dir_keep() {
+        let dir: super::TempDir =
+            super::TempDir::new_in(&std::env::temp_dir(), "dev-cli-test-keep").unwrap();
+        let path: std::path::PathBuf = dir.keep();
+        assert!(path.is_dir());
+        std::fs::remove_dir(path).unwrap();
+    }
 }
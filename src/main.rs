@@ -9,6 +9,8 @@ async fn main() {
         .about("A CLI for creating development projects")
         .subcommand(cli::commands::project())
         .subcommand(cli::commands::search())
+        .subcommand(cli::commands::cache())
+        .subcommand(cli::commands::doctor())
         .arg_required_else_help(true)
         .get_matches();
 